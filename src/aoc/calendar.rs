@@ -0,0 +1,228 @@
+use chrono::{DateTime, TimeZone, Utc};
+
+use super::{
+    day::Day, get_puzzle, part::Part, part_status::PartStatus, progress_report::ProgressReport,
+    year::Year,
+};
+
+/// Stars collected on the AoC servers for a single event, without any knowledge of local solutions
+#[derive(Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, Debug)]
+pub struct Calendar {
+    year: Year,
+    days: Vec<CalendarDay>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, Debug)]
+pub struct CalendarDay {
+    day: Day,
+    stars: u8,
+}
+
+impl CalendarDay {
+    pub fn day(&self) -> Day {
+        self.day
+    }
+    pub fn stars(&self) -> u8 {
+        self.stars
+    }
+}
+
+impl Calendar {
+    pub fn new<Y: Into<Year>>(year: Y) -> Self {
+        Self {
+            year: year.into(),
+            days: Vec::new(),
+        }
+    }
+
+    pub fn year(&self) -> Year {
+        self.year
+    }
+
+    pub fn days(&self) -> &[CalendarDay] {
+        &self.days
+    }
+
+    pub fn push<D: Into<Day>>(&mut self, day: D, stars: u8) {
+        self.days.push(CalendarDay {
+            day: day.into(),
+            stars,
+        });
+    }
+
+    /// Reads the stars from the day links of a calendar page.
+    /// Days are identified by their `calendar-dayN` class, the number of stars by the
+    /// `calendar-complete` and `calendar-verycomplete` classes, so the ASCII art is never looked at.
+    /// Locked days aren't links and are left out.
+    pub fn from_html<Y: Into<Year>>(year: Y, html: &str) -> Self {
+        let html = scraper::Html::parse_fragment(html);
+        let selector = scraper::Selector::parse("a[class]").unwrap();
+        let mut calendar = Self::new(year);
+        for link in html.select(&selector) {
+            let classes: Vec<&str> = link.value().classes().collect();
+            let Some(day) = classes
+                .iter()
+                .find_map(|class| class.strip_prefix("calendar-day"))
+                .and_then(|day| day.parse::<u8>().ok())
+            else {
+                continue;
+            };
+            let stars = if classes.contains(&"calendar-verycomplete") {
+                2
+            } else if classes.contains(&"calendar-complete") {
+                1
+            } else {
+                0
+            };
+            calendar.push(day, stars);
+        }
+        calendar.days.sort_by_key(|x| x.day);
+        calendar.days.dedup_by_key(|x| x.day);
+        calendar
+    }
+
+    /// Merges the server side stars with the currently registered solutions
+    pub fn progress(&self) -> Vec<ProgressReport> {
+        self.progress_at(Utc::now())
+    }
+
+    /// Like [`Calendar::progress`], days that unlock after `now` are reported as locked
+    pub fn progress_at(&self, now: DateTime<Utc>) -> Vec<ProgressReport> {
+        let mut data = Vec::new();
+        for day in (1..=25).map(Day::new) {
+            let locked = unlock_time(self.year, day).is_some_and(|x| x > now);
+            if locked && !self.days.iter().any(|x| x.day == day) {
+                for part in [Part::one(), Part::two()] {
+                    data.push(ProgressReport::new(
+                        self.year,
+                        day,
+                        part,
+                        PartStatus::Locked,
+                    ));
+                }
+            }
+        }
+        for day in &self.days {
+            for part in [Part::one(), Part::two()] {
+                let status = if day.stars >= part.as_u8() {
+                    PartStatus::Completed
+                } else if get_puzzle(self.year, day.day, part).is_some() {
+                    PartStatus::MissingWithSolution
+                } else {
+                    PartStatus::MissingWithNoSolution
+                };
+                data.push(ProgressReport::new(self.year, day.day, part, status));
+            }
+        }
+        data.sort_by_key(|x| (x.day(), x.part()));
+        data
+    }
+}
+
+/// Puzzles unlock at midnight US-Eastern, which is 05:00 UTC in December.
+/// Only days 1 to 25 exist, there is no unlock time for any other day.
+pub fn unlock_time<Y: Into<Year>, D: Into<Day>>(year: Y, day: D) -> Option<DateTime<Utc>> {
+    let (year, day) = (year.into(), day.into());
+    if !(1..=25).contains(&day.as_u8()) {
+        return None;
+    }
+    Utc.with_ymd_and_hms(year.as_u16().into(), 12, day.as_u8().into(), 5, 0, 0)
+        .single()
+}
+
+/// Days that don't exist are never unlocked
+pub fn is_unlocked<Y: Into<Year>, D: Into<Day>>(year: Y, day: D) -> bool {
+    unlock_time(year, day).is_some_and(|x| x <= Utc::now())
+}
+
+/// The first day of the year to unlock after `time`, a calendar fetched at `time` is outdated then
+pub fn next_unlock<Y: Into<Year>>(year: Y, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let year = year.into();
+    (1..=25u8)
+        .filter_map(|day| unlock_time(year, day))
+        .find(|x| *x > time)
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Duration, TimeZone, Utc};
+
+    use super::{is_unlocked, next_unlock, unlock_time, Calendar, PartStatus};
+
+    // The calendar fixtures are written by hand in the markup of the calendar pages, not
+    // captured from them. The art of 2019 and 2023 has digits besides the day numbers.
+    fn stars(calendar: &Calendar) -> Vec<(u8, u8)> {
+        calendar
+            .days()
+            .iter()
+            .map(|x| (x.day().as_u8(), x.stars()))
+            .collect()
+    }
+
+    #[test]
+    pub fn test_calendar_2015() {
+        let calendar = Calendar::from_html(2015, include_str!("../../fixtures/calendar/2015.html"));
+        assert_eq!(
+            stars(&calendar),
+            (1..=25).map(|d| (d, 2)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    pub fn test_calendar_2019() {
+        let calendar = Calendar::from_html(2019, include_str!("../../fixtures/calendar/2019.html"));
+        let mut expected = vec![(1, 2), (2, 2), (3, 1)];
+        expected.extend((4..=25).map(|d| (d, 0)));
+        assert_eq!(stars(&calendar), expected);
+    }
+
+    #[test]
+    pub fn test_unlock_time() {
+        let unlock = unlock_time(2023u16, 10u8).unwrap();
+        assert_eq!(unlock, Utc.with_ymd_and_hms(2023, 12, 10, 5, 0, 0).unwrap());
+        // December has days after the 25th, the event doesn't
+        for day in [0u8, 26, 40] {
+            assert_eq!(unlock_time(2023u16, day), None);
+            assert!(!is_unlocked(2023u16, day));
+        }
+        let calendar = Calendar::from_html(2023, include_str!("../../fixtures/calendar/2023.html"));
+        let locked = |now| {
+            calendar
+                .progress_at(now)
+                .iter()
+                .filter(|x| x.status() == PartStatus::Locked)
+                .count()
+        };
+        assert_eq!(locked(unlock - Duration::seconds(1)), 32);
+        assert_eq!(locked(unlock), 30);
+
+        assert_eq!(
+            next_unlock(2023u16, unlock - Duration::hours(1)),
+            Some(unlock)
+        );
+        assert_eq!(next_unlock(2023u16, unlock), unlock_time(2023u16, 11u8));
+        assert_eq!(
+            next_unlock(2023u16, unlock_time(2023u16, 25u8).unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    pub fn test_calendar_2023_locked_days() {
+        let calendar = Calendar::from_html(2023, include_str!("../../fixtures/calendar/2023.html"));
+        assert_eq!(
+            stars(&calendar),
+            vec![
+                (1, 2),
+                (2, 2),
+                (3, 2),
+                (4, 2),
+                (5, 1),
+                (6, 0),
+                (7, 0),
+                (8, 0),
+                (9, 0)
+            ]
+        );
+    }
+}
@@ -1,3 +1,16 @@
+use std::{
+    panic::catch_unwind,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use itertools::{EitherOrBoth, Itertools};
+
+use crate::util::{
+    parallel::map_ordered,
+    sandbox::{self, Limits},
+};
+
 use super::{day::Day, part::Part, puzzlespec::PuzzleSpec, year::Year, Puzzle, PuzzleTestInput};
 
 pub fn get_puzzle_test_inputs(puzzle: &Puzzle) -> impl Iterator<Item = PuzzleTestInput> {
@@ -30,25 +43,149 @@ pub fn get_puzzle<Y: Into<Year>, D: Into<Day>, P: Into<Part>>(
     get_puzzles(None).find(|x| x.year() == year && x.day() == day && x.part() == part)
 }
 
-/// Runs all registered Puzzle Self-Tests and outputs a list of puzzles that failed their self-test
-pub fn run_self_test(spec: Option<PuzzleSpec>) -> Vec<(Puzzle, PuzzleTestInput)> {
-    let mut wrong = Vec::new();
-    for puzzle in get_puzzles(spec) {
-        for test in get_puzzle_test_inputs(&puzzle) {
-            match puzzle.call(test.input(), Some(test.add())) {
-                Ok(out) if out == test.output() => {
-                    // good, all checks out
-                }
-                Ok(out) => {
-                    println!("Puzzle {puzzle} failed self test: Got {out:?} instead of {:?} from input {:?}", test.output(), test.input());
-                    wrong.push((puzzle.clone(), test))
-                }
-                Err(e) => {
-                    println!("Puzzle {puzzle} error'd during self-test: {e:?}");
-                    wrong.push((puzzle.clone(), test))
+/// How a single self-test went
+#[derive(Clone, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
+pub enum SelfTestOutcome {
+    Passed,
+    /// The solution returned something other than the expected output
+    Failed(String),
+    Error(String),
+    Panicked,
+    TimedOut(Duration),
+    /// The subprocess died, most likely by hitting the memory limit
+    Crashed(String),
+}
+
+/// Result of running one self-test of a puzzle
+#[derive(Clone, Debug)]
+pub struct SelfTestResult {
+    puzzle: Puzzle,
+    test: PuzzleTestInput,
+    /// Position of the test among the tests of its puzzle, starting at 1
+    index: usize,
+    outcome: SelfTestOutcome,
+    elapsed: Duration,
+}
+
+impl SelfTestResult {
+    pub fn puzzle(&self) -> &Puzzle {
+        &self.puzzle
+    }
+    pub fn test(&self) -> &PuzzleTestInput {
+        &self.test
+    }
+    pub fn outcome(&self) -> &SelfTestOutcome {
+        &self.outcome
+    }
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+    pub fn index(&self) -> usize {
+        self.index
+    }
+    pub fn passed(&self) -> bool {
+        self.outcome == SelfTestOutcome::Passed
+    }
+}
+
+impl std::fmt::Display for SelfTestResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = format!("{} #{}", self.puzzle, self.index);
+        match &self.outcome {
+            SelfTestOutcome::Passed => write!(f, "PASS {name} ({:?})", self.elapsed),
+            SelfTestOutcome::Failed(got) => {
+                writeln!(f, "FAIL {name} ({:?})", self.elapsed)?;
+                writeln!(f, "  input: {:?}", self.test.input())?;
+                // line based, outputs are mostly single numbers but some are rendered grids
+                for line in self.test.output().lines().zip_longest(got.lines()) {
+                    match line {
+                        EitherOrBoth::Both(expected, got) if expected == got => {
+                            writeln!(f, "    {expected}")?
+                        }
+                        EitherOrBoth::Both(expected, got) => {
+                            writeln!(f, "  - {expected}")?;
+                            writeln!(f, "  + {got}")?
+                        }
+                        EitherOrBoth::Left(expected) => writeln!(f, "  - {expected}")?,
+                        EitherOrBoth::Right(got) => writeln!(f, "  + {got}")?,
+                    }
                 }
+                Ok(())
             }
+            SelfTestOutcome::Error(e) => write!(f, "ERROR {name}: {e}"),
+            SelfTestOutcome::Panicked => write!(f, "PANIC {name}"),
+            SelfTestOutcome::TimedOut(timeout) => write!(f, "TIMEOUT {name} after {timeout:?}"),
+            SelfTestOutcome::Crashed(status) => write!(f, "CRASH {name} ({status})"),
+        }
+    }
+}
+
+/// Runs the registered self-tests of all puzzles matching the spec on up to `jobs` threads,
+/// each within the limits.
+/// With `fail_fast` no further tests are started after one doesn't pass, and the results
+/// end with the first failure.
+pub fn run_self_test(
+    spec: Option<PuzzleSpec>,
+    fail_fast: bool,
+    jobs: usize,
+    limits: Limits,
+) -> Vec<SelfTestResult> {
+    let tests: Vec<_> = get_puzzles(spec)
+        .flat_map(|puzzle| {
+            get_puzzle_test_inputs(&puzzle)
+                .enumerate()
+                .map(|(index, test)| (puzzle.clone(), test, index + 1))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    let failed = AtomicBool::new(false);
+    let results = map_ordered(jobs, tests, |(puzzle, test, index)| {
+        if fail_fast && failed.load(Ordering::Relaxed) {
+            return None;
+        }
+        let result = run_one_self_test(&puzzle, test, index, limits);
+        if !result.passed() {
+            failed.store(true, Ordering::Relaxed);
         }
+        Some(result)
+    });
+    let mut out = Vec::new();
+    for result in results.into_iter().flatten() {
+        let passed = result.passed();
+        out.push(result);
+        if fail_fast && !passed {
+            break;
+        }
+    }
+    out
+}
+
+/// Runs a single self-test within the limits, `index` is its position among the tests of the
+/// puzzle
+pub fn run_one_self_test(
+    puzzle: &Puzzle,
+    test: PuzzleTestInput,
+    index: usize,
+    limits: Limits,
+) -> SelfTestResult {
+    let start = Instant::now();
+    let outcome = sandbox::run_test(puzzle, &test, index, limits);
+    let elapsed = start.elapsed();
+    SelfTestResult {
+        puzzle: puzzle.clone(),
+        test,
+        index,
+        outcome,
+        elapsed,
+    }
+}
+
+/// Runs a single self-test in this process, only panics are caught
+pub fn self_test_outcome(puzzle: &Puzzle, test: &PuzzleTestInput) -> SelfTestOutcome {
+    match catch_unwind(|| puzzle.call(test.input(), Some(test.add()))) {
+        Ok(Ok(out)) if out == test.output() => SelfTestOutcome::Passed,
+        Ok(Ok(out)) => SelfTestOutcome::Failed(out),
+        Ok(Err(e)) => SelfTestOutcome::Error(format!("{e:?}")),
+        Err(_) => SelfTestOutcome::Panicked,
     }
-    wrong
 }
@@ -1,6 +1,7 @@
 mod year2019;
 mod year2023;
 
+pub mod calendar;
 pub mod day;
 mod exec;
 pub mod part;
@@ -9,13 +10,13 @@ pub mod progress_report;
 mod puzzle;
 mod puzzle_test_input;
 pub mod puzzlespec;
+#[cfg(test)]
+pub mod test_harness;
 pub mod year;
 
 pub use exec::*;
-pub use puzzle::puzzle;
-pub use puzzle_test_input::puzzle_test;
-
-use self::{puzzle::Puzzle, puzzle_test_input::PuzzleTestInput};
+pub use puzzle::{puzzle, Puzzle, Timings};
+pub use puzzle_test_input::{puzzle_test, PuzzleTestInput, TestSource};
 
 inventory::collect!(Puzzle);
 inventory::collect!(PuzzleTestInput);
@@ -1,4 +1,5 @@
 #[derive(Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
 pub enum PartStatus {
     /// This Part has been done and does not require solving again
     Completed,
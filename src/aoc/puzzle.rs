@@ -1,4 +1,4 @@
-use std::panic::RefUnwindSafe;
+use std::{panic::RefUnwindSafe, time::Duration};
 
 use crate::util::parsing::Solution;
 
@@ -20,8 +20,39 @@ pub const fn puzzle<S: Solution>(
     }
 }
 
-pub type Caller =
-    &'static (dyn Fn(&str, *const u8) -> anyhow::Result<String> + Send + Sync + RefUnwindSafe);
+pub type Caller = &'static (dyn Fn(&str, *const u8, &mut Timings) -> anyhow::Result<String>
+              + Send
+              + Sync
+              + RefUnwindSafe);
+
+/// Time spent in the phases of a single puzzle call
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct Timings {
+    /// Parsing the input with the `InputParser`
+    pub parse: Duration,
+    /// Unpacking the parsed input into the `InputType` of the solution
+    pub unpack: Duration,
+    /// Running the solution
+    pub solve: Duration,
+    /// Unpacking the result and formatting it
+    pub format: Duration,
+}
+
+impl Timings {
+    pub fn total(&self) -> Duration {
+        self.parse + self.unpack + self.solve + self.format
+    }
+}
+
+impl std::fmt::Display for Timings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "parse {:.2?}, unpack {:.2?}, solve {:.2?}, format {:.2?}",
+            self.parse, self.unpack, self.solve, self.format
+        )
+    }
+}
 
 #[derive(Clone)]
 pub struct Puzzle {
@@ -95,12 +126,23 @@ impl Puzzle {
         self.part
     }
     pub fn call(&self, inp: &str, add: Option<*const u8>) -> anyhow::Result<String> {
-        (self.call)(
+        self.call_timed(inp, add).map(|(out, _)| out)
+    }
+    /// Like [`Puzzle::call`], but also measures how long each phase of the call took
+    pub fn call_timed(
+        &self,
+        inp: &str,
+        add: Option<*const u8>,
+    ) -> anyhow::Result<(String, Timings)> {
+        let mut timings = Timings::default();
+        let out = (self.call)(
             inp,
             match add {
                 Some(add) => add,
                 None => self.add,
             },
-        )
+            &mut timings,
+        )?;
+        Ok((out, timings))
     }
 }
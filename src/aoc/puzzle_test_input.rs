@@ -9,6 +9,7 @@ pub const fn puzzle_test<S: Solution>(
     input: &'static str,
     add: &'static S::AdditionalArguments,
     output: &'static str,
+    source: TestSource,
 ) -> PuzzleTestInput {
     PuzzleTestInput {
         year,
@@ -17,6 +18,30 @@ pub const fn puzzle_test<S: Solution>(
         input,
         add: (add as *const S::AdditionalArguments) as *const u8,
         output,
+        source,
+    }
+}
+
+/// Where an `add_test!` was invoked, so the test harness can name and locate it
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TestSource {
+    module: &'static str,
+    file: &'static str,
+    line: u32,
+}
+
+impl TestSource {
+    pub const fn new(module: &'static str, file: &'static str, line: u32) -> Self {
+        Self { module, file, line }
+    }
+    pub fn module(&self) -> &'static str {
+        self.module
+    }
+    pub fn file(&self) -> &'static str {
+        self.file
+    }
+    pub fn line(&self) -> u32 {
+        self.line
     }
 }
 
@@ -28,6 +53,7 @@ pub struct PuzzleTestInput {
     input: &'static str,
     add: *const u8,
     output: &'static str,
+    source: TestSource,
 }
 
 impl PuzzleTestInput {
@@ -40,6 +66,12 @@ impl PuzzleTestInput {
     pub fn add(&self) -> *const u8 {
         self.add
     }
+    pub fn part(&self) -> Part {
+        self.part
+    }
+    pub fn source(&self) -> TestSource {
+        self.source
+    }
 }
 
 unsafe impl Sync for PuzzleTestInput {}
@@ -0,0 +1,74 @@
+//! Test runner that adds every `add_test!` registration to the `#[test]` functions,
+//! so `cargo test` and IDEs list the self-tests individually.
+
+use test::{
+    DynTestFn, DynTestName, ShouldPanic, StaticBenchFn, StaticTestFn, TestDesc, TestDescAndFn,
+    TestType,
+};
+
+use crate::util::sandbox::Limits;
+
+use super::{get_puzzle_test_inputs, get_puzzles, run_one_self_test, Puzzle, PuzzleTestInput};
+
+pub fn run_tests(tests: &[&TestDescAndFn]) {
+    let mut tests: Vec<TestDescAndFn> = tests.iter().map(|x| owned(x)).collect();
+    for puzzle in get_puzzles(None) {
+        for (index, test) in get_puzzle_test_inputs(&puzzle).enumerate() {
+            tests.push(self_test(puzzle.clone(), test, index + 1));
+        }
+    }
+    let args: Vec<String> = std::env::args().collect();
+    test::test_main(&args, tests, None)
+}
+
+fn owned(test: &TestDescAndFn) -> TestDescAndFn {
+    let testfn = match test.testfn {
+        StaticTestFn(f) => StaticTestFn(f),
+        StaticBenchFn(f) => StaticBenchFn(f),
+        _ => panic!("non-static tests passed to the test runner"),
+    };
+    TestDescAndFn {
+        desc: test.desc.clone(),
+        testfn,
+    }
+}
+
+/// Named after the module of the solution, the part and the line of the `add_test!`,
+/// like `aoc::year2019::day2::self_test_part1_line120`
+fn self_test(puzzle: Puzzle, test: PuzzleTestInput, index: usize) -> TestDescAndFn {
+    let source = test.source();
+    let module = source
+        .module()
+        .split_once("::")
+        .map(|(_, module)| module)
+        .unwrap_or(source.module());
+    let name = format!(
+        "{module}::self_test_part{}_line{}",
+        test.part(),
+        source.line()
+    );
+    TestDescAndFn {
+        desc: TestDesc {
+            name: DynTestName(name),
+            ignore: false,
+            ignore_message: None,
+            source_file: source.file(),
+            start_line: source.line() as usize,
+            start_col: 1,
+            end_line: source.line() as usize,
+            end_col: 1,
+            should_panic: ShouldPanic::No,
+            compile_fail: false,
+            no_run: false,
+            test_type: TestType::UnitTest,
+        },
+        testfn: DynTestFn(Box::new(move || {
+            let result = run_one_self_test(&puzzle, test, index, Limits::default());
+            if result.passed() {
+                Ok(())
+            } else {
+                Err(result.to_string())
+            }
+        })),
+    }
+}
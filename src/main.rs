@@ -2,17 +2,41 @@
 #![feature(const_trait_impl)]
 #![feature(associated_type_defaults)]
 #![feature(let_chains)]
+#![cfg_attr(test, feature(custom_test_frameworks, test))]
+#![cfg_attr(test, test_runner(crate::aoc::test_harness::run_tests))]
+
+#[cfg(test)]
+extern crate test;
 
 pub mod aoc;
 mod util;
 
-use std::panic::catch_unwind;
+use std::time::Duration;
 
 use aoc::get_puzzles;
-use clap::Parser;
-use util::{Action, Arguments};
+use clap::{CommandFactory, Parser};
+use util::{
+    bench::{bench, commit_label, Baseline, BenchSettings},
+    cache::CacheKey,
+    config::SubmitPolicy,
+    dashboard::DayOverview,
+    description::{to_markdown, to_text},
+    examples::{extract_examples, submitted_answers},
+    output::{
+        BenchRecord, CacheCountRecord, CacheEntryRecord, CrossCheckRecord, OutputFormat, Printer,
+        RunRecord, TestRecord,
+    },
+    parallel::map_ordered,
+    sandbox::{self, Outcome},
+    scaffold::scaffold_day,
+    Action, Arguments, CacheAction, PuzzleArg, RequiredPuzzleArg,
+};
 
-use crate::aoc::{part_status::PartStatus, run_self_test};
+use crate::aoc::{
+    calendar::unlock_time, day::Day, part::Part, part_status::PartStatus, puzzlespec::PuzzleSpec,
+    run_self_test,
+};
+use itertools::Itertools;
 
 #[macro_export]
 macro_rules! add_test {
@@ -25,6 +49,7 @@ macro_rules! add_test {
                 $inp,
                 $add,
                 $out,
+                $crate::aoc::TestSource::new(module_path!(), file!(), line!()),
             )
         }
     };
@@ -59,7 +84,7 @@ macro_rules! add_solution {
                 <$sol as Solution>::PUZZLE.1,
                 $part,
                 &$add,
-                &|inp: &str, add: *const u8| -> anyhow::Result<String> {
+                &|inp: &str, add: *const u8, timings: &mut $crate::aoc::Timings| -> anyhow::Result<String> {
                     use anyhow::Context;
                     use std::str::FromStr;
                     use $crate::util::parsing::Unpackable;
@@ -69,18 +94,26 @@ macro_rules! add_solution {
                     let add = add as *const <$sol as Solution>::AdditionalArguments;
                     let add: &<$sol as Solution>::AdditionalArguments = unsafe { &*add };
 
+                    let start = std::time::Instant::now();
                     let inp = <$sol as Solution>::InputParser::from_str(inp)
                         .context("parsing puzzle input failed")?;
+                    timings.parse = start.elapsed();
 
+                    let start = std::time::Instant::now();
                     let data = inp.unpack();
-
                     let data = <$sol as Solution>::Unpacker::unpacked(data);
+                    timings.unpack = start.elapsed();
 
+                    let start = std::time::Instant::now();
                     let out = <$sol as Solution>::$fun(data, *add)
                         .context("puzzle execution failed")?;
+                    timings.solve = start.elapsed();
 
+                    let start = std::time::Instant::now();
                     let out: <$sol as Solution>::Output = <$sol as Solution>::OutputResult::unpack(out);
-                    Ok(out.to_string())
+                    let out = out.to_string();
+                    timings.format = start.elapsed();
+                    Ok(out)
                 }
             )
         }
@@ -95,91 +128,429 @@ macro_rules! add_solution {
     };
 }
 
+/// Asks a yes/no question on the terminal, anything but yes is a no.
+/// The question goes to stderr, so it doesn't end up in the results.
+fn confirm(question: &str) -> anyhow::Result<bool> {
+    use std::io::Write;
+    eprint!("{question} [y/N] ");
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
 fn main() -> anyhow::Result<()> {
-    let arg = Arguments::parse();
-    if !run_self_test(arg.puzzle).is_empty() {
-        println!("Fix failing tests before submitting more puzzle solutions");
-        anyhow::bail!("self-test failure")
+    let arg = Arguments::parse().with_config()?;
+    let action = arg.action();
+    if action.needs_self_test() {
+        let run = action.run_args();
+        let failures: Vec<_> = run_self_test(action.puzzle(), false, run.jobs(), run.limits())
+            .into_iter()
+            .filter(|x| !x.passed())
+            .collect();
+        if !failures.is_empty() {
+            for failure in failures {
+                eprintln!("{failure}");
+            }
+            eprintln!("Fix failing tests before submitting more puzzle solutions");
+            anyhow::bail!("self-test failure")
+        }
     }
-    match arg.action {
-        Action::ListAoC => {
-            for puzzle in get_puzzles(arg.puzzle) {
-                println!(
-                    "Year {}, Day {}, Part {} = {}",
-                    puzzle.year(),
-                    puzzle.day(),
-                    puzzle.part(),
-                    arg.get_day_progress(puzzle.year(), puzzle.day(), puzzle.part())?
-                        .status()
-                );
+    match action {
+        Action::ListAoC {
+            puzzle: PuzzleArg { puzzle: spec },
+            filter,
+            ..
+        } => {
+            let mut printer = Printer::new(arg.format());
+            for progress in arg.list_progress(spec)? {
+                if filter.matches(&progress) {
+                    printer.print(&progress)?;
+                }
             }
+            printer.finish()?;
         }
-        Action::Benchmark => {
-            for puzzle in get_puzzles(arg.puzzle) {
+        Action::Benchmark {
+            puzzle: PuzzleArg { puzzle: spec },
+            bench_loops,
+            warm_up,
+            target_time,
+            save,
+            compare,
+            alloc,
+            bench_dir,
+            run,
+            ..
+        } => {
+            let settings = BenchSettings {
+                warm_up: Duration::from_millis(warm_up),
+                samples: arg.bench_loops(bench_loops),
+                target: Duration::from_millis(target_time),
+                alloc,
+            };
+            if let Some(Some(label)) = &save {
+                // reject a bad label before spending the time on the benchmarks
+                Baseline::path(&bench_dir, label)?;
+            }
+            let baseline = compare
+                .map(|label| Baseline::load(&bench_dir, &label))
+                .transpose()?;
+            let mut results = Baseline::default();
+            let mut records = Vec::new();
+            let mut printer = Printer::new(arg.format());
+            for puzzle in get_puzzles(spec) {
                 use anyhow::Context;
                 let input = arg
                     .get_input(puzzle.year(), puzzle.day())
                     .context("puzzle input gathering")?;
-                let start = std::time::Instant::now();
-                for _ in 0..arg.bench_loops() {
-                    let output = catch_unwind(|| -> anyhow::Result<String> {
-                        let solution = puzzle.call(&input, None).context("puzzle call")?;
-                        Ok(solution)
-                    });
-                    match output {
-                        Ok(Ok(solution)) => {
-                            let _ = std::hint::black_box(solution);
-                        }
-                        Ok(Err(e)) => {
-                            println!("Puzzle {puzzle} encountered an error: {e:?}");
-                            break;
-                        }
-                        Err(_) => {
-                            println!("Puzzle {puzzle} panicked");
-                            break;
-                        }
+                // a single run within the limits first, so a hanging solution can't hang the bench
+                let trial = if run.limits().is_unlimited() {
+                    Ok(())
+                } else {
+                    match sandbox::run(&puzzle, &input, run.limits()) {
+                        Outcome::Solved(..) => Ok(()),
+                        outcome => Err(format!("{outcome:?}")),
                     }
+                };
+                let report = trial
+                    .and_then(|()| bench(&puzzle, &input, settings).map_err(|e| format!("{e:#}")));
+                if let Ok(report) = &report {
+                    results.results.insert(puzzle.to_string(), *report);
                 }
-                println!(
-                    "Puzzle {puzzle} takes {:05.4} ns per invocation",
-                    start.elapsed().as_secs_f64() * 1_000_000_000.0 / (arg.bench_loops() as f64)
-                );
+                let before = baseline
+                    .as_ref()
+                    .and_then(|x| x.results.get(&puzzle.to_string()))
+                    .map(|x| x.total);
+                let record = BenchRecord::new(&puzzle, report, before);
+                printer.print(&record)?;
+                records.push(record);
+            }
+            printer.finish()?;
+            // plain lines are easy to miss among the others, so the comparison gets a table
+            if baseline.is_some() && arg.format() == OutputFormat::Plain {
+                println!();
+                let mut table = Printer::new(OutputFormat::Table);
+                for record in &records {
+                    table.print(record)?;
+                }
+                table.finish()?;
+                println!("{}", BenchRecord::summary(&records));
+            }
+            if let Some(label) = save {
+                let label = label
+                    .or_else(commit_label)
+                    .unwrap_or_else(|| "latest".to_owned());
+                results.save(&bench_dir, &label)?;
+                eprintln!("Saved results as {label}");
             }
-            println!("DONE");
         }
-        Action::Run => {
-            for puzzle in get_puzzles(arg.puzzle) {
+        Action::Run {
+            puzzle: PuzzleArg { puzzle: spec },
+            submit,
+            run,
+            ..
+        } => {
+            use anyhow::Context;
+            // inputs are fetched one by one, only the solutions run in parallel
+            let mut pending = Vec::new();
+            for puzzle in get_puzzles(spec) {
                 let progress = arg.get_day_progress(puzzle.year(), puzzle.day(), puzzle.part())?;
                 if progress.status() == PartStatus::Completed {
                     // skip days we finished
                     continue;
                 }
-                let output = catch_unwind(|| -> anyhow::Result<String> {
-                    use anyhow::Context;
-                    let input = arg
-                        .get_input(puzzle.year(), puzzle.day())
-                        .context("puzzle input gathering")?;
-                    let solution = puzzle.call(&input, None).context("puzzle call")?;
-                    Ok(solution)
-                });
-                match output {
-                    Ok(Ok(solution)) => {
-                        println!("Solution for {puzzle} = {solution}");
-                        println!(
-                            "Submission Result: {:?}",
-                            arg.submit_solution(
-                                puzzle.year(),
-                                puzzle.day(),
-                                puzzle.part(),
-                                solution
-                            )?
-                        );
+                let input = arg
+                    .get_input(puzzle.year(), puzzle.day())
+                    .context("puzzle input gathering")
+                    .map_err(|e| format!("{e:#}"));
+                pending.push((puzzle, input));
+            }
+            let limits = run.limits();
+            let outputs = map_ordered(run.jobs(), pending, |(puzzle, input)| {
+                let outcome = match input {
+                    Ok(input) => sandbox::run(&puzzle, &input, limits),
+                    Err(e) => Outcome::Error(e),
+                };
+                (puzzle, outcome)
+            });
+            // submissions stay sequential and in order
+            let mut printer = Printer::new(arg.format());
+            for (puzzle, outcome) in outputs {
+                let mut submission = None;
+                if let Outcome::Solved(solution, _) = &outcome {
+                    let submit = match arg.submit_policy(submit) {
+                        SubmitPolicy::Auto => true,
+                        SubmitPolicy::Confirm => {
+                            confirm(&format!("Submit {solution} for {puzzle}?"))?
+                        }
+                        SubmitPolicy::Never => false,
+                    };
+                    if submit {
+                        submission = Some(arg.submit_solution(
+                            puzzle.year(),
+                            puzzle.day(),
+                            puzzle.part(),
+                            solution.clone(),
+                        )?);
+                    }
+                }
+                printer.print(&RunRecord::new(&puzzle, outcome, submission))?;
+            }
+            printer.finish()?;
+            eprintln!("DONE");
+        }
+        Action::CrossCheck {
+            puzzle: PuzzleArg { puzzle: spec },
+            run,
+            ..
+        } => {
+            let profiles = arg.profiles()?;
+            let mut printer = Printer::new(arg.format());
+            for puzzle in get_puzzles(spec) {
+                for name in profiles.names() {
+                    let profile = arg.with_profile(name);
+                    let accepted =
+                        submitted_answers(&profile.get_puzzle_text(puzzle.year(), puzzle.day())?)
+                            .into_iter()
+                            .find(|(part, _)| *part == puzzle.part())
+                            .map(|(_, answer)| answer);
+                    let outcome = {
+                        use anyhow::Context;
+                        match profile
+                            .get_input(puzzle.year(), puzzle.day())
+                            .context("puzzle input gathering")
+                        {
+                            Ok(input) => sandbox::run(&puzzle, &input, run.limits()),
+                            Err(e) => Outcome::Error(format!("{e:#}")),
+                        }
+                    };
+                    printer.print(&CrossCheckRecord::new(&puzzle, name, outcome, accepted))?;
+                }
+            }
+            printer.finish()?;
+            eprintln!("DONE");
+        }
+        Action::Overview {
+            puzzle: PuzzleArg { puzzle: spec },
+            bench_dir,
+            run,
+        } => {
+            let benches = Baseline::latest(&bench_dir)?;
+            let years: Vec<_> = match spec.and_then(|x| x.year) {
+                Some(year) => vec![year],
+                None => get_puzzles(None).map(|x| x.year()).dedup().collect(),
+            };
+            // the overview is a table unless JSON is asked for
+            let format = match arg.format() {
+                OutputFormat::Json => OutputFormat::Json,
+                _ => OutputFormat::Table,
+            };
+            for year in years {
+                let spec = PuzzleSpec {
+                    year: Some(year),
+                    day: None,
+                    part: None,
+                };
+                let tests = run_self_test(Some(spec), false, run.jobs(), run.limits());
+                let mut printer = Printer::new(format);
+                let mut ready = Vec::new();
+                for day in (1..=25).map(Day::new) {
+                    let progress = [
+                        arg.get_day_progress(year, day, Part::one())?,
+                        arg.get_day_progress(year, day, Part::two())?,
+                    ];
+                    let overview = DayOverview::new(progress, &tests, &benches);
+                    ready.extend(overview.ready().map(|part| format!("{year}.{day}.{part}")));
+                    printer.print(&overview)?;
+                }
+                if format == OutputFormat::Table {
+                    println!("Year {year}");
+                }
+                printer.finish()?;
+                if format == OutputFormat::Table && !ready.is_empty() {
+                    println!("Ready to submit: {}", ready.join(", "));
+                }
+            }
+        }
+        Action::Test {
+            puzzle: PuzzleArg { puzzle: spec },
+            fail_fast,
+            run,
+        } => {
+            let results = run_self_test(spec, fail_fast, run.jobs(), run.limits());
+            let mut printer = Printer::new(arg.format());
+            for result in &results {
+                printer.print(&TestRecord::from(result))?;
+            }
+            printer.finish()?;
+            let failed = results.iter().filter(|x| !x.passed()).count();
+            if arg.format() == OutputFormat::Plain {
+                println!(
+                    "{} passed, {failed} failed in {:?}",
+                    results.len() - failed,
+                    results
+                        .iter()
+                        .map(|x| x.elapsed())
+                        .sum::<std::time::Duration>()
+                );
+            }
+            if failed > 0 {
+                anyhow::bail!("self-test failure")
+            }
+        }
+        Action::WaitFor { puzzle: spec } => {
+            use anyhow::Context;
+            let (Some(year), Some(day)) = (spec.year, spec.day) else {
+                anyhow::bail!("wait-for needs a year and a day");
+            };
+            let unlock = unlock_time(year, day)
+                .with_context(|| format!("{year} has no day {day}, only days 1 to 25"))?;
+            if let Ok(wait) = (unlock - chrono::Utc::now()).to_std() {
+                let secs = wait.as_secs();
+                eprintln!(
+                    "Waiting {}:{:02}:{:02} for {year}.{day} to unlock at {}",
+                    secs / 3600,
+                    secs / 60 % 60,
+                    secs % 60,
+                    unlock.with_timezone(&chrono::Local)
+                );
+                // sleep in short steps, so suspending the machine doesn't delay the fetch
+                while chrono::Utc::now() < unlock {
+                    let left = (unlock - chrono::Utc::now()).to_std().unwrap_or_default();
+                    std::thread::sleep(left.min(Duration::from_secs(1)));
+                }
+            }
+            // the local clock may be a little ahead of the server
+            let mut attempt = 1;
+            let input = loop {
+                match arg.get_input(year, day) {
+                    Ok(input) => break input,
+                    Err(e) if attempt < 5 => {
+                        eprintln!("Fetching the input failed, retrying: {e:#}");
+                        std::thread::sleep(Duration::from_secs(2));
+                        attempt += 1;
                     }
-                    Ok(Err(e)) => println!("Puzzle {puzzle} encountered an error: {e:?}"),
-                    Err(_) => println!("Puzzle {puzzle} panicked"),
+                    Err(e) => return Err(e),
+                }
+            };
+            eprintln!(
+                "Fetched {} lines of input for {year}.{day}",
+                input.lines().count()
+            );
+            let key = CacheKey::Input(year, day);
+            let mut printer = Printer::new(arg.format());
+            for entry in arg.cache()?.entries()?.iter().filter(|x| x.key() == key) {
+                printer.print(&CacheEntryRecord::new(entry, None))?;
+            }
+            printer.finish()?;
+        }
+        Action::Exec { puzzle, test } => sandbox::exec(puzzle, test)?,
+        Action::Completions { shell } => {
+            let mut cmd = Arguments::command();
+            let name = cmd.get_name().to_owned();
+            clap_complete::generate(shell, &mut cmd, name, &mut std::io::stdout());
+        }
+        Action::Read {
+            puzzle: RequiredPuzzleArg { puzzle: spec },
+            markdown,
+            width,
+        } => {
+            let (Some(year), Some(day)) = (spec.year, spec.day) else {
+                anyhow::bail!("reading a puzzle requires a year and day, like 2023.1");
+            };
+            let html = arg.get_puzzle_text(year, day)?;
+            let text = if markdown {
+                to_markdown(&html)
+            } else {
+                to_text(&html, width)
+            };
+            print!("{text}");
+        }
+        Action::Examples {
+            puzzle: RequiredPuzzleArg { puzzle: spec },
+            output,
+        } => {
+            let (Some(year), Some(day)) = (spec.year, spec.day) else {
+                anyhow::bail!("extracting examples requires a year and day, like 2023.1");
+            };
+            let html = arg.get_puzzle_text(year, day)?;
+            let candidates = extract_examples(&html)
+                .iter()
+                .filter(|x| spec.part.map(|part| part == x.part()).unwrap_or(true))
+                .map(|x| x.to_add_test("S"))
+                .collect::<Vec<_>>()
+                .join("\n");
+            match output {
+                Some(output) => std::fs::write(output, candidates + "\n")?,
+                None => println!("{candidates}"),
+            }
+        }
+        Action::New {
+            puzzle: RequiredPuzzleArg { puzzle: spec },
+            fetch,
+            src_dir,
+        } => {
+            let (Some(year), Some(day)) = (spec.year, spec.day) else {
+                anyhow::bail!("creating a new day requires a year and day, like 2023.1");
+            };
+            let tests = if fetch {
+                arg.get_input(year, day)?;
+                extract_examples(&arg.get_puzzle_text(year, day)?)
+                    .iter()
+                    .map(|x| x.to_add_test("S"))
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            let file = scaffold_day(&src_dir, year, day, &tests)?;
+            println!("Created {}", file.display());
+        }
+        Action::Cache(cache_action) => {
+            let cache = arg.cache()?;
+            match cache_action {
+                CacheAction::List {
+                    puzzle: PuzzleArg { puzzle: spec },
+                } => {
+                    if arg.format() == OutputFormat::Plain {
+                        println!("Cache at {}", cache.root().display());
+                    }
+                    let mut printer = Printer::new(arg.format());
+                    for entry in cache.entries_matching(&spec.unwrap_or_default())? {
+                        printer.print(&CacheEntryRecord::new(&entry, None))?;
+                    }
+                    printer.finish()?;
+                }
+                CacheAction::Show {
+                    puzzle: RequiredPuzzleArg { puzzle: spec },
+                } => {
+                    let mut printer = Printer::new(arg.format());
+                    for entry in cache.entries_matching(&spec)? {
+                        let content = cache.read(entry.key())?.unwrap_or_default();
+                        printer.print(&CacheEntryRecord::new(&entry, Some(content)))?;
+                    }
+                    printer.finish()?;
+                }
+                CacheAction::Clear {
+                    puzzle: RequiredPuzzleArg { puzzle: spec },
+                } => {
+                    let record = CacheCountRecord::new("clear", cache.clear(&spec)?, None);
+                    let mut printer = Printer::new(arg.format());
+                    printer.print(&record)?;
+                    printer.finish()?;
+                }
+                CacheAction::Export { dir } => {
+                    let record = CacheCountRecord::new("export", cache.export(&dir)?, Some(dir));
+                    let mut printer = Printer::new(arg.format());
+                    printer.print(&record)?;
+                    printer.finish()?;
+                }
+                CacheAction::Import { dir } => {
+                    let record = CacheCountRecord::new("import", cache.import(&dir)?, Some(dir));
+                    let mut printer = Printer::new(arg.format());
+                    printer.print(&record)?;
+                    printer.finish()?;
                 }
             }
-            println!("DONE");
         }
     }
 
@@ -1,19 +1,55 @@
+pub mod alloc;
+pub mod backend;
+pub mod bench;
+pub mod cache;
+pub mod config;
+pub mod dashboard;
+pub mod description;
+pub mod examples;
+pub mod output;
+pub mod parallel;
 pub mod parsing;
+pub mod profile;
+pub mod sandbox;
+pub mod scaffold;
+pub mod secret;
+#[cfg(test)]
+pub mod testing;
 
 use std::{
-    io::{Cursor, Seek, Write},
-    sync::Arc,
+    collections::{BTreeSet, HashSet},
+    path::PathBuf,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration,
 };
 
-use aoc_client::AocClient;
-
 use crate::aoc::{
-    day::Day, get_puzzle, part::Part, part_status::PartStatus, progress_report::ProgressReport,
-    puzzlespec::PuzzleSpec, year::Year,
+    calendar::{is_unlocked, next_unlock, unlock_time, Calendar},
+    day::Day,
+    get_puzzle, get_puzzles,
+    part::Part,
+    part_status::PartStatus,
+    progress_report::ProgressReport,
+    puzzlespec::PuzzleSpec,
+    year::Year,
 };
 use anyhow::{Context, Error};
+use itertools::Itertools;
+
+use self::{
+    backend::{AocBackend, ClientBackend, LocalBackend},
+    cache::{Cache, CacheKey},
+    config::{Config, SubmitPolicy},
+    examples::submitted_answers,
+    output::OutputFormat,
+    profile::{check_profile_name, Profiles},
+    sandbox::Limits,
+    secret::SecretString,
+};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SubmissionOutcome {
     Correct,
     Incorrect,
@@ -32,64 +68,527 @@ impl From<aoc_client::SubmissionOutcome> for SubmissionOutcome {
     }
 }
 
-#[repr(transparent)]
-pub struct SecretString(Arc<String>);
+/// Parses puzzle specs and offers every registered year, day and part for shell completion
+#[derive(Clone)]
+pub struct PuzzleSpecParser;
 
-impl std::str::FromStr for SecretString {
-    type Err = !;
+impl clap::builder::TypedValueParser for PuzzleSpecParser {
+    type Value = PuzzleSpec;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self(Arc::new(s.to_owned())))
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        _arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        value
+            .to_str()
+            .context("puzzle spec is not valid UTF-8")
+            .and_then(PuzzleSpec::from_str)
+            .map_err(|e| {
+                clap::Error::raw(
+                    clap::error::ErrorKind::ValueValidation,
+                    format!("invalid puzzle spec {value:?}: {e}\n"),
+                )
+                .with_cmd(cmd)
+            })
     }
-}
 
-impl SecretString {
-    pub fn unpack(self) -> String {
-        (*self.0).clone()
+    fn possible_values(
+        &self,
+    ) -> Option<Box<dyn Iterator<Item = clap::builder::PossibleValue> + '_>> {
+        let specs: BTreeSet<String> = get_puzzles(None)
+            .flat_map(|puzzle| {
+                let year = puzzle.year().as_u16();
+                let day = puzzle.day().as_u8();
+                [
+                    year.to_string(),
+                    format!("{year}.{day}"),
+                    format!("{year}.{day}.{}", puzzle.part()),
+                ]
+            })
+            .collect();
+        Some(Box::new(
+            specs.into_iter().map(clap::builder::PossibleValue::new),
+        ))
     }
 }
 
-impl Clone for SecretString {
-    fn clone(&self) -> Self {
-        Self(self.0.clone())
+/// Runs, tests and benchmarks Advent of Code solutions and submits their answers
+#[derive(clap::Parser, Clone)]
+pub struct Arguments {
+    /// Session token of adventofcode.com
+    #[clap(env, long, short, hide_env = true, global = true)]
+    aoc_token: Option<SecretString>,
+    /// Read the session token from a file only accessible by its owner
+    #[clap(env = "AOC_TOKEN_FILE", long, global = true)]
+    aoc_token_file: Option<PathBuf>,
+    /// Look the session token up in the secret service, see `secret-tool store service adventofcode.com`
+    #[clap(long, global = true)]
+    secret_service: bool,
+    #[clap(subcommand)]
+    action: Option<Action>,
+    /// Directory used to cache inputs and calendars, defaults to a scratch directory of the build
+    #[clap(env = "AOC_CACHE_DIR", long, global = true)]
+    cache_dir: Option<PathBuf>,
+    /// Seconds for which cached calendar progress is trusted before fetching it again
+    #[clap(env = "AOC_PROGRESS_TTL", long, default_value = "900", global = true)]
+    progress_ttl: u64,
+    /// Fetch calendar progress again even if the cached copy is still fresh
+    #[clap(long, global = true)]
+    refresh: bool,
+    /// Serve inputs, calendars and submissions from a local directory instead of adventofcode.com
+    #[clap(env = "AOC_BACKEND_DIR", long, global = true)]
+    backend_dir: Option<PathBuf>,
+    /// Use the token and cache of a profile from the profiles file, even if a token is given
+    #[clap(env = "AOC_PROFILE", long, global = true)]
+    profile: Option<String>,
+    /// File with the named profiles, defaults to `aoc/profiles.toml` in the user config directory
+    #[clap(env = "AOC_PROFILES", long, global = true)]
+    profiles_file: Option<PathBuf>,
+    /// How results are printed, defaults to plain
+    #[clap(env = "AOC_FORMAT", long, value_enum, global = true)]
+    format: Option<OutputFormat>,
+    /// Defaults from the `aoc.toml` files
+    #[clap(skip)]
+    config: Config,
+    /// Years whose calendar was already refreshed during this run
+    #[clap(skip)]
+    refreshed: Arc<Mutex<HashSet<Year>>>,
+}
+
+/// How the actions running solutions and self-tests run them
+#[derive(Debug, clap::Args, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RunArgs {
+    /// Threads used to run puzzles and self-tests, defaults to the number of CPUs.
+    /// Benchmarks always run one at a time.
+    #[clap(env = "AOC_JOBS", long, short)]
+    jobs: Option<usize>,
+    /// Seconds a solution or self-test may run before it is killed and reported as timed out
+    #[clap(env = "AOC_TIMEOUT", long, value_parser = parse_seconds)]
+    timeout: Option<Duration>,
+    /// Megabytes of memory a solution may use, only enforced on unix
+    #[clap(env = "AOC_MEMORY_LIMIT", long)]
+    memory_limit: Option<u64>,
+}
+
+fn parse_seconds(value: &str) -> Result<Duration, Error> {
+    Ok(Duration::try_from_secs_f64(value.parse()?)?)
+}
+
+impl RunArgs {
+    pub fn jobs(&self) -> usize {
+        self.jobs
+            .or_else(|| std::thread::available_parallelism().ok().map(Into::into))
+            .unwrap_or(1)
+    }
+
+    /// Limits for running solutions and self-tests, which then run in a subprocess each
+    pub fn limits(&self) -> Limits {
+        Limits {
+            timeout: self.timeout,
+            memory: self.memory_limit.map(|x| x * 1024 * 1024),
+        }
     }
 }
 
-#[derive(clap::Parser)]
-pub struct Arguments {
-    #[clap(env, long, short, hide_env = true)]
-    aoc_token: SecretString,
-    pub action: Action,
-    #[clap(value_parser = clap::value_parser!(PuzzleSpec))]
+/// A puzzle spec, parts left out match everything and no spec at all matches every puzzle
+#[derive(Debug, clap::Args, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PuzzleArg {
+    /// Like 2023, 2023.1 or 2023.1.2, all puzzles if left out
+    #[clap(value_parser = PuzzleSpecParser, value_name = "YEAR.DAY.PART", hide_possible_values = true)]
     pub puzzle: Option<PuzzleSpec>,
-    #[clap(env, long, short, default_value = "1000")]
-    pub bench_loops: u32,
 }
 
-#[derive(Default, Debug, clap::ValueEnum, Clone, Copy, PartialEq, Eq)]
+/// A puzzle spec that has to be given
+#[derive(Debug, clap::Args, Clone, Copy, PartialEq, Eq)]
+pub struct RequiredPuzzleArg {
+    /// Like 2023, 2023.1 or 2023.1.2
+    #[clap(value_parser = PuzzleSpecParser, value_name = "YEAR.DAY.PART", hide_possible_values = true)]
+    pub puzzle: PuzzleSpec,
+}
+
+#[derive(Debug, clap::Subcommand, Clone, PartialEq, Eq)]
 pub enum Action {
-    #[default]
+    /// List the puzzles of the calendar and of local solutions and whether they are solved.
+    /// Filters combine, so only puzzles matching all of them are listed.
     #[clap(name = "list-aoc", alias = "list")]
-    ListAoC,
+    ListAoC {
+        #[command(flatten)]
+        puzzle: PuzzleArg,
+        #[command(flatten)]
+        filter: ProgressFilter,
+        #[command(flatten)]
+        run: RunArgs,
+        /// Don't run the self-tests before running puzzle solutions
+        #[clap(long)]
+        skip_self_test: bool,
+    },
+    /// Run the solutions of unsolved puzzles and submit their answers
     #[clap(name = "run")]
-    Run,
+    Run {
+        #[command(flatten)]
+        puzzle: PuzzleArg,
+        /// Whether solutions are submitted, defaults to auto
+        #[clap(env = "AOC_SUBMIT", long, value_enum)]
+        submit: Option<SubmitPolicy>,
+        #[command(flatten)]
+        run: RunArgs,
+        /// Don't run the self-tests before running puzzle solutions
+        #[clap(long)]
+        skip_self_test: bool,
+    },
+    /// Measure how long solutions take on their inputs
     #[clap(name = "bench", alias = "benchmark")]
-    Benchmark,
+    Benchmark {
+        #[command(flatten)]
+        puzzle: PuzzleArg,
+        /// Samples per benchmarked puzzle, by default as many as fit into the target time
+        #[clap(env, long, short)]
+        bench_loops: Option<u32>,
+        /// Milliseconds to run a puzzle before measuring it
+        #[clap(long, default_value = "100")]
+        warm_up: u64,
+        /// Milliseconds to spend sampling each puzzle when no sample count is given
+        #[clap(long, default_value = "1000")]
+        target_time: u64,
+        /// Save the results under this label, or the current commit if none is given
+        #[clap(long, value_name = "LABEL")]
+        save: Option<Option<String>>,
+        /// Compare the results with those saved under this label
+        #[clap(long)]
+        compare: Option<String>,
+        /// Also report allocations, bytes allocated and peak heap usage per call
+        #[clap(long)]
+        alloc: bool,
+        /// Directory the results are saved in
+        #[clap(long, default_value = concat!(env!("CARGO_MANIFEST_DIR"), "/target/aoc-bench"))]
+        bench_dir: PathBuf,
+        #[command(flatten)]
+        run: RunArgs,
+        /// Don't run the self-tests before running puzzle solutions
+        #[clap(long)]
+        skip_self_test: bool,
+    },
+    /// Show a calendar of every year with stars, solutions, self-tests and benchmarks
+    #[clap(name = "overview", alias = "dashboard")]
+    Overview {
+        #[command(flatten)]
+        puzzle: PuzzleArg,
+        /// Directory benchmark results are saved in
+        #[clap(long, default_value = concat!(env!("CARGO_MANIFEST_DIR"), "/target/aoc-bench"))]
+        bench_dir: PathBuf,
+        #[command(flatten)]
+        run: RunArgs,
+    },
+    /// Run the self-tests of solutions against their examples
+    #[clap(name = "test")]
+    Test {
+        #[command(flatten)]
+        puzzle: PuzzleArg,
+        /// Stop at the first test that doesn't pass
+        #[clap(long)]
+        fail_fast: bool,
+        #[command(flatten)]
+        run: RunArgs,
+    },
+    /// Show the description of a puzzle, including part 2 once it is unlocked
+    #[clap(name = "read")]
+    Read {
+        #[command(flatten)]
+        puzzle: RequiredPuzzleArg,
+        /// Render as Markdown instead of plain terminal text
+        #[clap(long)]
+        markdown: bool,
+        /// Line width of the plain text, Markdown isn't wrapped
+        #[clap(long, default_value = "80")]
+        width: usize,
+    },
+    /// Print the examples of a puzzle description as `add_test!` candidates
+    #[clap(name = "examples")]
+    Examples {
+        #[command(flatten)]
+        puzzle: RequiredPuzzleArg,
+        /// Write the candidates into a file instead of printing them
+        #[clap(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Create the solution file of a new day and declare its module
+    #[clap(name = "new")]
+    New {
+        #[command(flatten)]
+        puzzle: RequiredPuzzleArg,
+        /// Also fetch the input and add the examples of the description as self-tests
+        #[clap(long)]
+        fetch: bool,
+        /// Source directory of this crate
+        #[clap(long, default_value = concat!(env!("CARGO_MANIFEST_DIR"), "/src"))]
+        src_dir: PathBuf,
+    },
+    /// Run solutions on the inputs of every profile and compare with their accepted answers
+    #[clap(name = "cross-check")]
+    CrossCheck {
+        #[command(flatten)]
+        puzzle: PuzzleArg,
+        #[command(flatten)]
+        run: RunArgs,
+        /// Don't run the self-tests before running puzzle solutions
+        #[clap(long)]
+        skip_self_test: bool,
+    },
+    /// Inspect and manage the cache of inputs and calendars
+    #[clap(name = "cache", subcommand)]
+    Cache(CacheAction),
+    /// Print a shell completion script
+    #[clap(name = "completions")]
+    Completions { shell: clap_complete::Shell },
+    /// Sleep until a day unlocks and fetch its input right away
+    #[clap(name = "wait-for")]
+    WaitFor {
+        #[clap(value_parser = PuzzleSpecParser, value_name = "YEAR.DAY", hide_possible_values = true)]
+        puzzle: PuzzleSpec,
+    },
+    /// Run a single puzzle on the input from stdin, used to run puzzles within limits
+    #[clap(name = "exec", hide = true)]
+    Exec {
+        puzzle: PuzzleSpec,
+        /// Run this self-test of the puzzle instead, counting from 1
+        #[clap(long)]
+        test: Option<usize>,
+    },
+}
+
+impl Default for Action {
+    fn default() -> Self {
+        Self::ListAoC {
+            puzzle: PuzzleArg::default(),
+            filter: ProgressFilter::default(),
+            run: RunArgs::default(),
+            skip_self_test: false,
+        }
+    }
+}
+
+/// Narrows down the listed puzzles
+#[derive(Debug, clap::Args, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProgressFilter {
+    /// Only puzzles that aren't solved on the server yet
+    #[clap(long)]
+    pub missing: bool,
+    /// Only puzzles without a local solution
+    #[clap(long)]
+    pub unsolved_locally: bool,
+    /// Only puzzles that are still locked
+    #[clap(long)]
+    pub locked: bool,
+}
+
+impl ProgressFilter {
+    pub fn matches(&self, progress: &ProgressReport) -> bool {
+        let status = progress.status();
+        let missing = matches!(
+            status,
+            PartStatus::MissingWithSolution | PartStatus::MissingWithNoSolution
+        );
+        let solved_locally = get_puzzle(progress.year(), progress.day(), progress.part()).is_some();
+        (!self.missing || missing)
+            && (!self.unsolved_locally || !solved_locally)
+            && (!self.locked || status == PartStatus::Locked)
+    }
+}
+
+impl Action {
+    /// The spec of the actions taking an optional one, the only list of them to maintain
+    fn puzzle_arg(&mut self) -> Option<&mut PuzzleArg> {
+        match self {
+            Action::ListAoC { puzzle, .. }
+            | Action::Run { puzzle, .. }
+            | Action::Benchmark { puzzle, .. }
+            | Action::Test { puzzle, .. }
+            | Action::Overview { puzzle, .. }
+            | Action::CrossCheck { puzzle, .. } => Some(puzzle),
+            _ => None,
+        }
+    }
+
+    pub fn puzzle(&self) -> Option<PuzzleSpec> {
+        self.clone().puzzle_arg().and_then(|x| x.puzzle)
+    }
+
+    /// Uses `spec` for actions that take an optional spec but weren't given one
+    pub fn with_default_puzzle(mut self, spec: Option<PuzzleSpec>) -> Self {
+        if let Some(arg) = self.puzzle_arg() {
+            arg.puzzle = arg.puzzle.or(spec);
+        }
+        self
+    }
+
+    /// How solutions and self-tests are run, the defaults for actions that don't run them
+    pub fn run_args(&self) -> RunArgs {
+        match self {
+            Action::ListAoC { run, .. }
+            | Action::Run { run, .. }
+            | Action::Benchmark { run, .. }
+            | Action::Overview { run, .. }
+            | Action::Test { run, .. }
+            | Action::CrossCheck { run, .. } => *run,
+            _ => RunArgs::default(),
+        }
+    }
+
+    /// Whether this action executes puzzle solutions and thus needs passing self-tests first
+    pub fn needs_self_test(&self) -> bool {
+        match self {
+            Action::ListAoC { skip_self_test, .. }
+            | Action::Run { skip_self_test, .. }
+            | Action::Benchmark { skip_self_test, .. }
+            | Action::CrossCheck { skip_self_test, .. } => !skip_self_test,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, clap::Subcommand, Clone, PartialEq, Eq)]
+pub enum CacheAction {
+    /// List cached entries, optionally only those matching a puzzle spec
+    #[clap(name = "list")]
+    List {
+        #[command(flatten)]
+        puzzle: PuzzleArg,
+    },
+    /// Print the contents of all cached entries matching a puzzle spec
+    #[clap(name = "show")]
+    Show {
+        #[command(flatten)]
+        puzzle: RequiredPuzzleArg,
+    },
+    /// Remove all cached entries matching a puzzle spec, use `*` to clear everything
+    #[clap(name = "clear")]
+    Clear {
+        #[command(flatten)]
+        puzzle: RequiredPuzzleArg,
+    },
+    /// Copy all cached entries into a directory
+    #[clap(name = "export")]
+    Export { dir: PathBuf },
+    /// Copy cached entries from a directory into the cache
+    #[clap(name = "import")]
+    Import { dir: PathBuf },
 }
 
 impl Arguments {
-    fn get_client<Y: Into<Year>, D: Into<Day>>(&self, year: Y, day: D) -> Result<AocClient, Error> {
-        let year: Year = year.into();
-        let day: Day = day.into();
-        Ok(aoc_client::AocClient::builder()
-            .session_cookie(self.aoc_token.clone().unpack())?
-            .year(year.as_u16().into())?
-            .day(day.as_u8().into())?
-            .build()?)
+    fn backend(&self) -> Result<Box<dyn AocBackend>, Error> {
+        if let Some(dir) = &self.backend_dir {
+            return Ok(Box::new(LocalBackend::new(dir.clone())));
+        }
+        let token = self.token()?;
+        token.validate()?;
+        Ok(Box::new(ClientBackend::new(token)))
+    }
+
+    /// An explicit `--profile` wins over the token flags, which win over the configured and
+    /// the default profile
+    fn token(&self) -> Result<SecretString, Error> {
+        if let Some(profile) = self.account_profile()? {
+            return Ok(self.profiles()?.get(&profile)?.token());
+        }
+        if let Some(token) = &self.aoc_token {
+            return Ok(token.clone());
+        }
+        if let Some(path) = &self.aoc_token_file {
+            return SecretString::from_file(path);
+        }
+        if self.secret_service {
+            return SecretString::from_secret_service();
+        }
+        anyhow::bail!(
+            "no session token given, set --aoc-token, --aoc-token-file, --secret-service or a profile"
+        )
+    }
+
+    /// The profile whose token is used, if the token doesn't come from the token flags
+    fn account_profile(&self) -> Result<Option<String>, Error> {
+        if self.profile.is_some() {
+            return self.profile_name();
+        }
+        if self.aoc_token.is_some() || self.aoc_token_file.is_some() || self.secret_service {
+            return Ok(None);
+        }
+        self.profile_name()
+    }
+
+    pub fn profiles(&self) -> Result<Profiles, Error> {
+        match self.profiles_file.clone().or_else(Profiles::default_path) {
+            Some(path) => Profiles::load(&path),
+            None => Ok(Profiles::default()),
+        }
+    }
+
+    /// The selected profile, falling back to the default of the profiles file
+    pub fn profile_name(&self) -> Result<Option<String>, Error> {
+        match self.profile.as_ref().or(self.config.profile.as_ref()) {
+            Some(profile) => Ok(Some(profile.clone())),
+            None => Ok(self.profiles()?.default_profile().map(ToOwned::to_owned)),
+        }
+    }
+
+    /// The same arguments, but using the token and cache of another profile
+    pub fn with_profile(&self, profile: &str) -> Self {
+        let mut arg = self.clone();
+        arg.aoc_token = None;
+        arg.aoc_token_file = None;
+        arg.secret_service = false;
+        arg.profile = Some(profile.to_owned());
+        arg.refreshed = Default::default();
+        arg
+    }
+
+    /// Reads the `aoc.toml` files, their settings apply wherever no flag or variable is given
+    pub fn with_config(mut self) -> Result<Self, Error> {
+        self.config = Config::load()?;
+        Ok(self)
+    }
+
+    pub fn action(&self) -> Action {
+        self.action
+            .clone()
+            .unwrap_or_default()
+            .with_default_puzzle(self.config.spec)
+    }
+
+    /// Every profile gets its own namespace in the cache, inputs differ between accounts.
+    /// Tokens given by flag share the namespace without a profile.
+    pub fn cache(&self) -> Result<Cache, Error> {
+        let root = self
+            .cache_dir
+            .clone()
+            .or_else(|| self.config.cache_dir.clone())
+            .unwrap_or_else(Cache::default_root);
+        match self.account_profile()? {
+            Some(profile) => {
+                check_profile_name(&profile)?;
+                Cache::new(root.join(format!("profile-{profile}")))
+            }
+            None => Cache::new(root),
+        }
+    }
+
+    pub fn progress_ttl(&self) -> Duration {
+        Duration::from_secs(self.progress_ttl)
+    }
+
+    pub fn format(&self) -> OutputFormat {
+        self.format.or(self.config.format).unwrap_or_default()
+    }
+
+    /// `loops` given to the bench action, else the configured default
+    pub fn bench_loops(&self, loops: Option<u32>) -> Option<u32> {
+        loops.or(self.config.bench_loops)
     }
 
-    pub fn bench_loops(&self) -> u32 {
-        self.bench_loops
+    /// `policy` given to the run action, else the configured default
+    pub fn submit_policy(&self, policy: Option<SubmitPolicy>) -> SubmitPolicy {
+        policy.or(self.config.submit).unwrap_or_default()
     }
 
     pub fn submit_solution<
@@ -106,134 +605,148 @@ impl Arguments {
     ) -> Result<SubmissionOutcome, Error> {
         let data: String = data.into();
         let part: Part = part.into();
-        let client = self.get_client(year, day)?;
-        let result: SubmissionOutcome = client.submit_answer(part.as_i64(), data)?.into();
+        let result = self
+            .backend()?
+            .submit(year.into(), day.into(), part, &data)?;
 
-        let mut cache = scratch::path("aoc_req");
-        let year: Year = year.into();
-        let day: Day = day.into();
-        cache.push(format!("year{year}_day{day}_input"));
-
-        if cache.exists() {
-            std::fs::remove_file(cache)?;
-        }
-
-        let mut cache = scratch::path("aoc_req");
-        cache.push(format!("year{year}_calendar"));
-
-        if cache.exists() {
-            std::fs::remove_file(cache)?;
+        // the input never changes but the stars on the calendar might have
+        let cache = self.cache()?;
+        cache.remove(CacheKey::Calendar(year.into()))?;
+        if result == SubmissionOutcome::Correct {
+            // solving a part unlocks the next one in the description
+            cache.remove(CacheKey::Puzzle(year.into(), day.into()))?;
         }
 
         Ok(result)
     }
 
     pub fn get_input<Y: Into<Year>, D: Into<Day>>(&self, year: Y, day: D) -> Result<String, Error> {
-        let mut cache = scratch::path("aoc_req");
         let year: Year = year.into();
         let day: Day = day.into();
-        cache.push(format!("year{year}_day{day}_input"));
+        let cache = self.cache()?;
+        let key = CacheKey::Input(year, day);
 
-        if let Ok(file) = std::fs::File::open(cache.clone()) {
-            Ok(std::io::read_to_string(file)?)
+        if let Some(data) = cache.read(key)? {
+            Ok(data)
         } else {
-            let data = self.get_client(year, day)?.get_input()?;
-            let mut cache = std::fs::File::options()
-                .read(true)
-                .write(true)
-                .create(true)
-                .append(true)
-                .open(cache.clone())
-                .context("could not create cache file")?;
-
-            cache
-                .write_all(data.as_bytes())
-                .context("writing cache data")?;
-
-            cache.flush().context("flushing write to disk")?;
+            ensure_unlocked(year, day)?;
+            let data = self.backend()?.fetch_input(year, day)?;
+            cache.write(key, &data).context("writing cache data")?;
+            Ok(data)
+        }
+    }
 
-            cache
-                .seek(std::io::SeekFrom::Start(0))
-                .context("seeking back to start")?;
+    /// The HTML of the puzzle description, cached next to the input
+    pub fn get_puzzle_text<Y: Into<Year>, D: Into<Day>>(
+        &self,
+        year: Y,
+        day: D,
+    ) -> Result<String, Error> {
+        let year: Year = year.into();
+        let day: Day = day.into();
+        let cache = self.cache()?;
+        let key = CacheKey::Puzzle(year, day);
 
-            Ok(std::io::read_to_string(cache)?)
+        let cached = cache
+            .read(key)?
+            .filter(|data| !self.puzzle_outdated(year, day, data));
+        if let Some(data) = cached {
+            Ok(data)
+        } else {
+            ensure_unlocked(year, day)?;
+            let data = self.backend()?.fetch_puzzle(year, day)?;
+            cache.write(key, &data).context("writing cache data")?;
+            Ok(data)
         }
     }
 
-    pub fn get_progress<Y: Into<Year>>(&self, year: Y) -> Result<Vec<ProgressReport>, Error> {
+    /// Whether the calendar has more stars for the day than the description has answers,
+    /// parts solved elsewhere add part 2 and the answers to the description.
+    /// Without a calendar the description is kept as it is.
+    fn puzzle_outdated(&self, year: Year, day: Day, html: &str) -> bool {
+        let Ok(calendar) = self.get_calendar(year) else {
+            return false;
+        };
+        let stars = calendar
+            .days()
+            .iter()
+            .find(|x| x.day() == day)
+            .map_or(0, |x| x.stars());
+        // the second star of day 25 comes without a puzzle answer
+        let answers = if day == 25 { stars.min(1) } else { stars };
+        submitted_answers(html).len() < answers.into()
+    }
+
+    /// The stars collected on the server, cached for the progress TTL
+    pub fn get_calendar<Y: Into<Year>>(&self, year: Y) -> Result<Calendar, Error> {
         let year: Year = year.into();
-        let mut cache = scratch::path("aoc_req");
-        cache.push(format!("year{year}_calendar"));
-        let file = if let Ok(file) = std::fs::File::open(cache.clone()) {
-            file
-        } else {
-            let cal = self.get_client(year, 1)?;
-            let cal = cal.get_calendar_html()?;
-            let cal = Cursor::new(cal);
-            let cal = html2text::from_read_with_decorator(
-                cal,
-                80,
-                html2text::render::text_renderer::TrivialDecorator::new(),
-            );
-            let regex = regex::Regex::new(r#"(?P<day>\d{1,2})\s(?P<p1>\*(?P<p2>\*|$)|$)"#).unwrap();
-            let mut data = Vec::new();
-            for line in cal.lines() {
-                if let Some(captures) = regex.captures(line) {
-                    let day: u8 = captures.name("day").unwrap().as_str().parse()?;
-                    let first_part = captures.name("p1").map(|x| x.as_str()).unwrap_or_default();
-                    let _second_part = captures.name("p2").map(|x| x.as_str()).unwrap_or_default();
-                    data.push(ProgressReport::new(
-                        year,
-                        day,
-                        Part::one(),
-                        if first_part == "*" || first_part == "**" {
-                            PartStatus::Completed
-                        } else if get_puzzle(year, day, Part::one()).is_some() {
-                            PartStatus::MissingWithSolution
-                        } else {
-                            PartStatus::MissingWithNoSolution
-                        },
-                    ));
-                    data.push(ProgressReport::new(
-                        year,
-                        day,
-                        Part::two(),
-                        if first_part == "**" {
-                            PartStatus::Completed
-                        } else if get_puzzle(year, day, Part::two()).is_some() {
-                            PartStatus::MissingWithSolution
-                        } else {
-                            PartStatus::MissingWithNoSolution
-                        },
-                    ));
-                }
+        // the server has no calendar before the event starts, every day is locked then
+        if !is_unlocked(year, 1u8) {
+            return Ok(Calendar::new(year));
+        }
+        let cache = self.cache()?;
+        let key = CacheKey::Calendar(year);
+        // a day unlocking since the fetch isn't on the cached calendar yet
+        let expired = cache
+            .metadata(key)?
+            .map(|x| {
+                x.fetched_at().elapsed().unwrap_or_default() > self.progress_ttl()
+                    || next_unlock(year, x.fetched_at().into())
+                        .is_some_and(|x| x <= chrono::Utc::now())
+            })
+            .unwrap_or(true);
+        let refresh = self.refresh && !self.refreshed.lock().unwrap().contains(&year);
+        if !expired && !refresh {
+            // entries written by older versions don't parse and are simply fetched again
+            if let Some(calendar) = cache
+                .read(key)?
+                .and_then(|data| serde_json::from_str(&data).ok())
+            {
+                return Ok(calendar);
             }
-            let data = serde_json::to_string_pretty(&data)
-                .context("could not serialize progress data to cache")?;
+        }
 
-            let mut cache = std::fs::File::options()
-                .read(true)
-                .write(true)
-                .create(true)
-                .append(true)
-                .open(cache.clone())
-                .context("could not create cache file")?;
+        self.refreshed.lock().unwrap().insert(year);
+        let cal = self.backend()?.fetch_calendar(year)?;
+        let calendar = Calendar::from_html(year, &cal);
+        let data = serde_json::to_string_pretty(&calendar)
+            .context("could not serialize calendar to cache")?;
 
-            cache
-                .write_all(data.as_bytes())
-                .context("writing cache data")?;
+        cache.write(key, &data).context("writing cache data")?;
 
-            cache.flush().context("flushing write to disk")?;
+        Ok(calendar)
+    }
 
-            cache
-                .seek(std::io::SeekFrom::Start(0))
-                .context("seeking back to start")?;
+    pub fn get_progress<Y: Into<Year>>(&self, year: Y) -> Result<Vec<ProgressReport>, Error> {
+        Ok(self.get_calendar(year)?.progress())
+    }
 
-            cache
+    /// Progress of the calendar merged with the registered solutions, for every year of the
+    /// spec or every year with solutions
+    pub fn list_progress(&self, spec: Option<PuzzleSpec>) -> Result<Vec<ProgressReport>, Error> {
+        let spec = spec.unwrap_or_default();
+        let years: Vec<Year> = match spec.year {
+            Some(year) => vec![year],
+            None => get_puzzles(None).map(|x| x.year()).dedup().collect(),
         };
-        let file: Vec<ProgressReport> =
-            serde_json::from_reader(file).context("could not deserialize from reader")?;
-        Ok(file)
+        let mut progress = Vec::new();
+        for year in years {
+            progress.extend(self.get_progress(year)?);
+        }
+        // solutions of days the calendar doesn't show yet
+        for puzzle in get_puzzles(Some(spec)) {
+            if !progress.iter().any(|x| {
+                x.year() == puzzle.year() && x.day() == puzzle.day() && x.part() == puzzle.part()
+            }) {
+                progress.push(self.get_day_progress(puzzle.year(), puzzle.day(), puzzle.part())?);
+            }
+        }
+        progress.retain(|x| {
+            spec.day.map(|day| day == x.day()).unwrap_or(true)
+                && spec.part.map(|part| part == x.part()).unwrap_or(true)
+        });
+        progress.sort_by_key(|x| (x.year(), x.day(), x.part()));
+        Ok(progress)
     }
 
     pub fn get_day_progress<
@@ -250,6 +763,303 @@ impl Arguments {
         Ok(progresses
             .into_iter()
             .find(|x| year == x.year() && day == x.day() && part == x.part())
-            .unwrap_or_else(|| ProgressReport::new(year, day, part, PartStatus::Unavailable)))
+            .unwrap_or_else(|| {
+                let status = if is_unlocked(year.into(), day.into()) {
+                    PartStatus::Unavailable
+                } else {
+                    PartStatus::Locked
+                };
+                ProgressReport::new(year, day, part, status)
+            }))
+    }
+}
+
+/// Days that aren't unlocked yet have neither an input nor a description
+fn ensure_unlocked(year: Year, day: Day) -> Result<(), Error> {
+    let unlock = unlock_time(year, day)
+        .with_context(|| format!("{year} has no day {day}, only days 1 to 25"))?;
+    if unlock > chrono::Utc::now() {
+        anyhow::bail!(
+            "{year}.{day} is locked until {}",
+            unlock.with_timezone(&chrono::Local)
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(unix)]
+    use std::os::unix::fs::PermissionsExt;
+
+    use chrono::{TimeZone, Utc};
+    use clap::Parser;
+
+    use super::{
+        testing::offline_args, Action, Arguments, CacheKey, ProgressFilter, PuzzleSpecParser,
+    };
+    use crate::aoc::{part_status::PartStatus, progress_report::ProgressReport};
+
+    #[test]
+    #[cfg(unix)]
+    pub fn test_profile_over_token() {
+        let dir = tempfile::tempdir().unwrap();
+        let profiles = dir.path().join("profiles.toml");
+        std::fs::write(
+            &profiles,
+            "default = \"alice\"\n[profiles.alice]\ntoken = \"alice\"\n[profiles.bob]\ntoken = \"bob\"",
+        )
+        .unwrap();
+        std::fs::set_permissions(&profiles, std::fs::Permissions::from_mode(0o600)).unwrap();
+        let cache = dir.path().join("cache");
+        let parse = |args: &[&str]| {
+            let common = [
+                "aoc",
+                "--profiles-file",
+                profiles.to_str().unwrap(),
+                "--cache-dir",
+                cache.to_str().unwrap(),
+            ];
+            Arguments::parse_from(common.iter().chain(args))
+        };
+        let arg = parse(&["--aoc-token", "global", "--profile", "bob"]);
+        assert_eq!(arg.token().unwrap().expose(), "bob");
+        assert_eq!(arg.cache().unwrap().root(), cache.join("profile-bob"));
+        let arg = parse(&["--aoc-token", "global"]);
+        assert_eq!(arg.token().unwrap().expose(), "global");
+        assert_eq!(arg.cache().unwrap().root(), cache);
+        let arg = parse(&[]);
+        assert_eq!(arg.token().unwrap().expose(), "alice");
+        assert_eq!(arg.cache().unwrap().root(), cache.join("profile-alice"));
+        assert!(parse(&["--profile", "../bob"]).cache().is_err());
+    }
+
+    #[test]
+    pub fn test_refetch_corrupted() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = dir.path().join("backend");
+        std::fs::create_dir_all(backend.join("2019/day01")).unwrap();
+        std::fs::write(backend.join("2019/day01/input"), "12\n").unwrap();
+        let arg = offline_args(dir.path(), &[]);
+        assert_eq!(arg.get_input(2019u16, 1u8).unwrap(), "12\n");
+        let cache = arg.cache().unwrap();
+        std::fs::write(cache.path(CacheKey::Input(2019.into(), 1.into())), "1").unwrap();
+        assert_eq!(arg.get_input(2019u16, 1u8).unwrap(), "12\n");
+    }
+
+    #[test]
+    pub fn test_list_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = dir.path().join("backend");
+        // 2023 from a calendar page fixture, 2019 from the days present in the backend
+        std::fs::create_dir_all(backend.join("2023")).unwrap();
+        std::fs::write(
+            backend.join("2023/calendar.html"),
+            include_str!("../fixtures/calendar/2023.html"),
+        )
+        .unwrap();
+        std::fs::create_dir_all(backend.join("2019/day01")).unwrap();
+        std::fs::write(backend.join("2019/day01/part1.solved"), "").unwrap();
+        let arg = offline_args(dir.path(), &[]);
+        let progress = arg.list_progress(None).unwrap();
+        let name = |x: &ProgressReport| format!("{}.{}.{}", x.year(), x.day(), x.part());
+        let status = |spec: &str| {
+            progress
+                .iter()
+                .find(|x| name(x) == spec)
+                .map(|x| x.status())
+                .unwrap()
+        };
+        // day 2 of 2019 has a solution but isn't on the calendar
+        assert_eq!(status("2019.01.1"), PartStatus::Completed);
+        assert_eq!(status("2019.01.2"), PartStatus::MissingWithSolution);
+        assert_eq!(status("2019.02.2"), PartStatus::Unavailable);
+        // days 5 to 9 of 2023 are on the calendar without a solution
+        assert_eq!(status("2023.04.2"), PartStatus::Completed);
+        assert_eq!(status("2023.05.1"), PartStatus::Completed);
+        assert_eq!(status("2023.05.2"), PartStatus::MissingWithNoSolution);
+        assert_eq!(progress.len(), 4 + 18);
+
+        let listed = |filter: ProgressFilter| {
+            progress
+                .iter()
+                .filter(|x| filter.matches(x))
+                .map(name)
+                .collect::<Vec<_>>()
+        };
+        let missing = ProgressFilter {
+            missing: true,
+            ..Default::default()
+        };
+        let unsolved_locally = ProgressFilter {
+            unsolved_locally: true,
+            ..Default::default()
+        };
+        let locked = ProgressFilter {
+            locked: true,
+            ..Default::default()
+        };
+        assert_eq!(listed(ProgressFilter::default()).len(), progress.len());
+        assert_eq!(listed(missing).len(), 10);
+        assert_eq!(listed(missing)[0], "2019.01.2");
+        assert_eq!(listed(unsolved_locally).len(), 10);
+        assert_eq!(listed(unsolved_locally)[0], "2023.05.1");
+        let both = ProgressFilter {
+            missing: true,
+            unsolved_locally: true,
+            ..Default::default()
+        };
+        assert_eq!(listed(both).len(), 9);
+        assert_eq!(listed(both)[0], "2023.05.2");
+        assert!(listed(locked).is_empty());
+
+        let locked_day = ProgressReport::new(2023u16, 12u8, 1u8, PartStatus::Locked);
+        assert!(locked.matches(&locked_day));
+        assert!(unsolved_locally.matches(&locked_day));
+        assert!(!missing.matches(&locked_day));
+        assert!(!ProgressFilter {
+            missing: true,
+            ..locked
+        }
+        .matches(&locked_day));
+    }
+
+    #[test]
+    pub fn test_flag_positions() {
+        use clap::CommandFactory;
+
+        Arguments::command().debug_assert();
+        let parse = |args: &[&str]| {
+            Arguments::try_parse_from(std::iter::once("aoc").chain(args.iter().copied()))
+        };
+        // shared flags go before or after the action
+        for args in [
+            &["--format", "json", "run", "2019.1"][..],
+            &["run", "2019.1", "--format", "json"],
+        ] {
+            let arg = parse(args).unwrap();
+            assert_eq!(arg.format(), super::OutputFormat::Json);
+        }
+        assert!(parse(&["list", "--refresh", "--cache-dir", "/tmp"]).is_ok());
+
+        // run settings belong to the actions running solutions
+        let arg = parse(&["test", "--jobs", "4", "--timeout", "1.5"]).unwrap();
+        let run = arg.action().run_args();
+        assert_eq!(run.jobs(), 4);
+        assert_eq!(
+            run.limits().timeout,
+            Some(std::time::Duration::from_millis(1500))
+        );
+        assert!(!arg.action().needs_self_test());
+        assert!(parse(&["run", "--timeout", "-1"]).is_err());
+        assert!(parse(&["--jobs", "4", "run"]).is_err());
+        assert!(parse(&["read", "2019.1", "--skip-self-test"]).is_err());
+        let arg = parse(&["run", "--skip-self-test"]).unwrap();
+        assert!(matches!(arg.action(), Action::Run { .. }));
+        assert!(!arg.action().needs_self_test());
+        assert!(parse(&["bench"]).unwrap().action().needs_self_test());
+    }
+
+    #[test]
+    pub fn test_puzzle_spec_parser() {
+        use clap::builder::TypedValueParser;
+
+        let cmd = clap::Command::new("aoc");
+        let parse = |value: &str| PuzzleSpecParser.parse_ref(&cmd, None, value.as_ref());
+        let spec = parse("2019.1.2").unwrap();
+        assert_eq!(
+            (spec.year, spec.day, spec.part),
+            (Some(2019.into()), Some(1.into()), Some(2.into()))
+        );
+        let spec = parse("*.1").unwrap();
+        assert_eq!(
+            (spec.year, spec.day, spec.part),
+            (None, Some(1.into()), None)
+        );
+        for value in ["2019.x", "2019.1.y", "2019.300", "a.b.c.d"] {
+            let error = parse(value).unwrap_err();
+            assert_eq!(error.kind(), clap::error::ErrorKind::ValueValidation);
+        }
+
+        let values: Vec<_> = PuzzleSpecParser
+            .possible_values()
+            .unwrap()
+            .map(|x| x.get_name().to_owned())
+            .collect();
+        for value in ["2019", "2019.1", "2019.1.1", "2019.1.2", "2023.4.2"] {
+            assert!(values.iter().any(|x| x == value), "{value}");
+        }
+        assert!(!values.iter().any(|x| x == "2019.3"));
+
+        // the spec is optional for listing but required for reading
+        let action = |args: &[&str]| Arguments::parse_from(["aoc"].iter().chain(args)).action();
+        assert_eq!(action(&["list"]).puzzle(), None);
+        assert_eq!(
+            action(&["run", "2019.1"]).puzzle(),
+            Some(parse("2019.1").unwrap())
+        );
+        assert_eq!(
+            action(&["run"]).with_default_puzzle(Some(spec)).puzzle(),
+            Some(spec)
+        );
+        assert!(Arguments::try_parse_from(["aoc", "read"]).is_err());
+        assert!(Arguments::try_parse_from(["aoc", "list", "2019.x"]).is_err());
+    }
+
+    #[test]
+    pub fn test_calendar_outdated_by_unlock() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = dir.path().join("backend");
+        std::fs::create_dir_all(backend.join("2023")).unwrap();
+        let calendar = backend.join("2023/calendar.html");
+        std::fs::write(&calendar, include_str!("../fixtures/calendar/2023.html")).unwrap();
+        let arg = offline_args(dir.path(), &["--progress-ttl", "100000000000"]);
+        assert_eq!(arg.get_calendar(2023u16).unwrap().days().len(), 9);
+        std::fs::write(&calendar, include_str!("../fixtures/calendar/2015.html")).unwrap();
+        let fetched_at = |time: i64| {
+            let path = arg.cache().unwrap().root().join("year2023_calendar.meta");
+            let mut meta: serde_json::Value =
+                serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+            meta["fetched_at"] = time.into();
+            std::fs::write(&path, meta.to_string()).unwrap();
+        };
+        // fetched after the last day unlocked, the cached calendar is still good
+        fetched_at(
+            Utc.with_ymd_and_hms(2023, 12, 26, 0, 0, 0)
+                .unwrap()
+                .timestamp(),
+        );
+        assert_eq!(arg.get_calendar(2023u16).unwrap().days().len(), 9);
+        // fetched before day 10 unlocked
+        fetched_at(
+            Utc.with_ymd_and_hms(2023, 12, 9, 6, 0, 0)
+                .unwrap()
+                .timestamp(),
+        );
+        assert_eq!(arg.get_calendar(2023u16).unwrap().days().len(), 25);
+    }
+
+    #[test]
+    pub fn test_puzzle_solved_elsewhere() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = dir.path().join("backend");
+        let day = backend.join("2019/day01");
+        std::fs::create_dir_all(&day).unwrap();
+        std::fs::write(day.join("puzzle.html"), "<p>Part one</p>").unwrap();
+        let arg = offline_args(dir.path(), &["--progress-ttl", "0"]);
+        assert_eq!(
+            arg.get_puzzle_text(2019u16, 1u8).unwrap(),
+            "<p>Part one</p>"
+        );
+        // without a new star the cached description is kept
+        let solved = "<p>Your puzzle answer was <code>34239</code>.</p><p>Part two</p>";
+        std::fs::write(day.join("puzzle.html"), solved).unwrap();
+        assert_eq!(
+            arg.get_puzzle_text(2019u16, 1u8).unwrap(),
+            "<p>Part one</p>"
+        );
+        // a part submitted in the browser shows up as a star on the calendar
+        std::fs::write(day.join("part1.solved"), "").unwrap();
+        assert_eq!(arg.get_puzzle_text(2019u16, 1u8).unwrap(), solved);
     }
 }
@@ -0,0 +1,223 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Error};
+use aoc_client::AocClient;
+
+use crate::aoc::{day::Day, part::Part, year::Year};
+
+use super::{secret::SecretString, SubmissionOutcome};
+
+/// Everything the runner needs from the AoC servers
+pub trait AocBackend {
+    fn fetch_input(&self, year: Year, day: Day) -> Result<String, Error>;
+    /// The HTML of the event calendar, see [`crate::aoc::calendar::Calendar::from_html`]
+    fn fetch_calendar(&self, year: Year) -> Result<String, Error>;
+    /// The HTML of the puzzle description, containing part 2 once it is unlocked
+    fn fetch_puzzle(&self, year: Year, day: Day) -> Result<String, Error>;
+    fn submit(
+        &self,
+        year: Year,
+        day: Day,
+        part: Part,
+        answer: &str,
+    ) -> Result<SubmissionOutcome, Error>;
+}
+
+/// Talks to adventofcode.com using the session token
+pub struct ClientBackend {
+    token: SecretString,
+}
+
+impl ClientBackend {
+    pub fn new(token: SecretString) -> Self {
+        Self { token }
+    }
+
+    fn client(&self, year: Year, day: Day) -> Result<AocClient, Error> {
+        Ok(aoc_client::AocClient::builder()
+            .session_cookie(self.token.expose())?
+            .year(year.as_u16().into())?
+            .day(day.as_u8().into())?
+            .build()?)
+    }
+}
+
+impl AocBackend for ClientBackend {
+    fn fetch_input(&self, year: Year, day: Day) -> Result<String, Error> {
+        Ok(self.client(year, day)?.get_input()?)
+    }
+
+    fn fetch_calendar(&self, year: Year) -> Result<String, Error> {
+        Ok(self.client(year, Day::new(1))?.get_calendar_html()?)
+    }
+
+    fn fetch_puzzle(&self, year: Year, day: Day) -> Result<String, Error> {
+        Ok(self.client(year, day)?.get_puzzle_html()?)
+    }
+
+    fn submit(
+        &self,
+        year: Year,
+        day: Day,
+        part: Part,
+        answer: &str,
+    ) -> Result<SubmissionOutcome, Error> {
+        Ok(self
+            .client(year, day)?
+            .submit_answer(part.as_i64(), answer)?
+            .into())
+    }
+}
+
+/// Serves requests from a directory instead of the AoC servers, for working offline and testing.
+///
+/// The directory is laid out as `{year}/day{day}/input`, `{year}/day{day}/puzzle.html` and
+/// `{year}/day{day}/part{part}.answer`. Correct submissions leave a `part{part}.solved` marker.
+/// Unless a `{year}/calendar.html` exists, the calendar is generated from the day directories
+/// and their markers.
+pub struct LocalBackend {
+    root: PathBuf,
+}
+
+impl LocalBackend {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    fn year_dir(&self, year: Year) -> PathBuf {
+        self.root.join(year.as_u16().to_string())
+    }
+
+    fn day_dir(&self, year: Year, day: Day) -> PathBuf {
+        self.year_dir(year).join(format!("day{day}"))
+    }
+
+    fn read(&self, path: PathBuf) -> Result<String, Error> {
+        std::fs::read_to_string(&path).with_context(|| format!("could not read {}", path.display()))
+    }
+
+    fn solved(&self, year: Year, day: Day, part: Part) -> bool {
+        self.day_dir(year, day)
+            .join(format!("part{part}.solved"))
+            .exists()
+    }
+}
+
+impl AocBackend for LocalBackend {
+    fn fetch_input(&self, year: Year, day: Day) -> Result<String, Error> {
+        self.read(self.day_dir(year, day).join("input"))
+    }
+
+    fn fetch_calendar(&self, year: Year) -> Result<String, Error> {
+        let calendar = self.year_dir(year).join("calendar.html");
+        if calendar.exists() {
+            return self.read(calendar);
+        }
+        let mut html = String::from("<pre class=\"calendar\">\n");
+        for day in 1..=25u8 {
+            let day = Day::new(day);
+            if !self.day_dir(year, day).exists() {
+                continue;
+            }
+            let class = if self.solved(year, day, Part::two()) {
+                " calendar-verycomplete"
+            } else if self.solved(year, day, Part::one()) {
+                " calendar-complete"
+            } else {
+                ""
+            };
+            html.push_str(&format!(
+                "<a href=\"/{}/day/{}\" class=\"calendar-day{}{class}\"><span class=\"calendar-day\">{day}</span></a>\n",
+                year.as_u16(),
+                day.as_u8(),
+                day.as_u8(),
+            ));
+        }
+        html.push_str("</pre>\n");
+        Ok(html)
+    }
+
+    fn fetch_puzzle(&self, year: Year, day: Day) -> Result<String, Error> {
+        self.read(self.day_dir(year, day).join("puzzle.html"))
+    }
+
+    fn submit(
+        &self,
+        year: Year,
+        day: Day,
+        part: Part,
+        answer: &str,
+    ) -> Result<SubmissionOutcome, Error> {
+        let previous_solved = part == Part::one() || self.solved(year, day, Part::one());
+        if self.solved(year, day, part) || !previous_solved {
+            return Ok(SubmissionOutcome::WrongLevel);
+        }
+        let expected = self.read(self.day_dir(year, day).join(format!("part{part}.answer")))?;
+        if expected.trim() != answer.trim() {
+            return Ok(SubmissionOutcome::Incorrect);
+        }
+        std::fs::write(
+            self.day_dir(year, day).join(format!("part{part}.solved")),
+            answer,
+        )
+        .context("could not mark part as solved")?;
+        Ok(SubmissionOutcome::Correct)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        aoc::{day::day, get_puzzle, part::Part, part_status::PartStatus, year::year},
+        util::{testing::offline_args, SubmissionOutcome},
+    };
+
+    #[test]
+    pub fn test_offline_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let day_dir = dir.path().join("backend").join("2019").join("day01");
+        std::fs::create_dir_all(&day_dir).unwrap();
+        std::fs::write(day_dir.join("input"), "12\n1969").unwrap();
+        std::fs::write(day_dir.join("part1.answer"), "656").unwrap();
+        std::fs::write(day_dir.join("part2.answer"), "968").unwrap();
+
+        let arg = offline_args(dir.path(), &["run"]);
+
+        let progress = arg
+            .get_day_progress(year(2019), day(1), Part::one())
+            .unwrap();
+        assert_eq!(progress.status(), PartStatus::MissingWithSolution);
+
+        let input = arg.get_input(year(2019), day(1)).unwrap();
+        assert_eq!(input, "12\n1969");
+
+        assert_eq!(
+            arg.submit_solution(year(2019), day(1), Part::two(), "968")
+                .unwrap(),
+            SubmissionOutcome::WrongLevel
+        );
+        assert_eq!(
+            arg.submit_solution(year(2019), day(1), Part::one(), "1")
+                .unwrap(),
+            SubmissionOutcome::Incorrect
+        );
+        let solution = get_puzzle(year(2019), day(1), Part::one())
+            .unwrap()
+            .call(&input, None)
+            .unwrap();
+        assert_eq!(
+            arg.submit_solution(year(2019), day(1), Part::one(), solution)
+                .unwrap(),
+            SubmissionOutcome::Correct
+        );
+
+        let progress = arg
+            .get_day_progress(year(2019), day(1), Part::one())
+            .unwrap();
+        assert_eq!(progress.status(), PartStatus::Completed);
+        let progress = arg
+            .get_day_progress(year(2019), day(1), Part::two())
+            .unwrap();
+        assert_eq!(progress.status(), PartStatus::MissingWithSolution);
+    }
+}
@@ -0,0 +1,367 @@
+use std::{
+    collections::BTreeMap,
+    panic::catch_unwind,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context, Error};
+
+use crate::aoc::{Puzzle, Timings};
+
+use super::alloc::{self, AllocStats};
+
+/// How long and how often a puzzle is benchmarked
+#[derive(Clone, Copy, Debug)]
+pub struct BenchSettings {
+    /// Calls before measuring starts, to warm up caches and branch predictors
+    pub warm_up: Duration,
+    /// Fixed number of samples, otherwise as many as fit into `target`
+    pub samples: Option<u32>,
+    pub target: Duration,
+    /// Also count the allocations of a call
+    pub alloc: bool,
+}
+
+impl BenchSettings {
+    const MIN_SAMPLES: u64 = 10;
+    const MAX_SAMPLES: u64 = 1_000_000;
+
+    /// Estimates how many samples fit into the target time from the warm-up calls
+    fn samples(&self, warm_up_calls: u64, warm_up_time: Duration) -> u64 {
+        if let Some(samples) = self.samples {
+            return samples.into();
+        }
+        let per_call = warm_up_time.as_nanos() / u128::from(warm_up_calls.max(1));
+        let samples = self.target.as_nanos() / per_call.max(1);
+        (samples as u64).clamp(Self::MIN_SAMPLES, Self::MAX_SAMPLES)
+    }
+}
+
+/// Summary of a set of samples
+#[derive(Clone, Copy, PartialEq, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct Stats {
+    pub samples: usize,
+    pub min: Duration,
+    pub median: Duration,
+    pub mean: Duration,
+    pub stddev: Duration,
+    /// Samples outside of 1.5 times the interquartile range
+    pub outliers: usize,
+}
+
+impl Stats {
+    pub fn new(samples: &[Duration]) -> Self {
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+        let nanos: Vec<f64> = sorted.iter().map(|x| x.as_nanos() as f64).collect();
+        let n = nanos.len().max(1) as f64;
+        let mean = nanos.iter().sum::<f64>() / n;
+        let variance = nanos.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+        let quantile = |q: f64| {
+            nanos
+                .get((nanos.len().saturating_sub(1) as f64 * q) as usize)
+                .copied()
+        };
+        let outliers = match (quantile(0.25), quantile(0.75)) {
+            (Some(q1), Some(q3)) => {
+                let fence = (q3 - q1) * 1.5;
+                nanos
+                    .iter()
+                    .filter(|x| **x < q1 - fence || **x > q3 + fence)
+                    .count()
+            }
+            _ => 0,
+        };
+        Self {
+            samples: sorted.len(),
+            min: sorted.first().copied().unwrap_or_default(),
+            median: sorted.get(sorted.len() / 2).copied().unwrap_or_default(),
+            mean: Duration::from_nanos(mean as u64),
+            stddev: Duration::from_nanos(variance.sqrt() as u64),
+            outliers,
+        }
+    }
+}
+
+impl std::fmt::Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "median {:.2?}, min {:.2?}, mean {:.2?} ± {:.2?}",
+            self.median, self.min, self.mean, self.stddev
+        )
+    }
+}
+
+/// Benchmark results of one puzzle, split into the phases of the call
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+pub struct BenchReport {
+    pub total: Stats,
+    pub parse: Stats,
+    #[serde(default)]
+    pub unpack: Stats,
+    pub solve: Stats,
+    #[serde(default)]
+    pub format: Stats,
+    /// Heap usage of a single call, when measured with `--alloc`
+    #[serde(default)]
+    pub alloc: Option<AllocStats>,
+}
+
+impl std::fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({} samples, {} outliers), parse {:.2?}, unpack {:.2?}, solve {:.2?}, format {:.2?}",
+            self.total,
+            self.total.samples,
+            self.total.outliers,
+            self.parse.median,
+            self.unpack.median,
+            self.solve.median,
+            self.format.median
+        )?;
+        if let Some(alloc) = self.alloc {
+            write!(f, ", {alloc}")?;
+        }
+        Ok(())
+    }
+}
+
+fn call(puzzle: &Puzzle, input: &str) -> Result<Timings, Error> {
+    match catch_unwind(|| puzzle.call_timed(input, None)) {
+        Ok(Ok((out, timings))) => {
+            std::hint::black_box(out);
+            Ok(timings)
+        }
+        Ok(Err(e)) => Err(e),
+        Err(_) => anyhow::bail!("puzzle panicked"),
+    }
+}
+
+/// Warms up, then samples every call separately
+pub fn bench(puzzle: &Puzzle, input: &str, settings: BenchSettings) -> Result<BenchReport, Error> {
+    let start = Instant::now();
+    let mut warm_up_calls = 0;
+    while warm_up_calls == 0 || start.elapsed() < settings.warm_up {
+        call(puzzle, input)?;
+        warm_up_calls += 1;
+    }
+    let samples = settings.samples(warm_up_calls, start.elapsed());
+
+    let mut timings = Vec::with_capacity(samples as usize);
+    for _ in 0..samples {
+        timings.push(call(puzzle, input)?);
+    }
+    let phase =
+        |f: fn(&Timings) -> Duration| Stats::new(&timings.iter().map(f).collect::<Vec<_>>());
+    Ok(BenchReport {
+        total: phase(Timings::total),
+        parse: phase(|x| x.parse),
+        unpack: phase(|x| x.unpack),
+        solve: phase(|x| x.solve),
+        format: phase(|x| x.format),
+        // measured separately, so counting doesn't slow down the timed calls
+        alloc: settings
+            .alloc
+            .then(|| alloc::measure(|| call(puzzle, input)))
+            .map(|(out, stats)| out.map(|_| stats))
+            .transpose()?,
+    })
+}
+
+/// How a benchmark changed compared to a baseline
+#[derive(Clone, Copy, PartialEq, Eq, Debug, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Change {
+    Improvement,
+    Regression,
+    /// Within noise, or not significant
+    Unchanged,
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Change::Improvement => "improved",
+            Change::Regression => "REGRESSED",
+            Change::Unchanged => "",
+        })
+    }
+}
+
+impl Stats {
+    /// Relative change of the mean compared to `baseline`, positive when slower
+    pub fn relative_change(&self, baseline: &Stats) -> f64 {
+        let old = baseline.mean.as_nanos() as f64;
+        (self.mean.as_nanos() as f64 - old) / old.max(1.0)
+    }
+
+    /// Welch's t-test on the means at 99% confidence. Changes below 2% are treated as noise
+    /// even when significant, timer resolution and frequency scaling cause those easily.
+    pub fn compare(&self, baseline: &Stats) -> Change {
+        let variance = |x: &Stats| (x.stddev.as_nanos() as f64).powi(2) / x.samples.max(1) as f64;
+        let diff = self.mean.as_nanos() as f64 - baseline.mean.as_nanos() as f64;
+        let t = diff
+            / (variance(self) + variance(baseline))
+                .sqrt()
+                .max(f64::EPSILON);
+        let change = self.relative_change(baseline);
+        if t.abs() < 2.576 || change.abs() < 0.02 {
+            Change::Unchanged
+        } else if change > 0.0 {
+            Change::Regression
+        } else {
+            Change::Improvement
+        }
+    }
+}
+
+/// Benchmark results stored under a label, usually the commit they were measured on
+#[derive(Clone, Default, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Baseline {
+    #[serde(default)]
+    pub results: BTreeMap<String, BenchReport>,
+}
+
+impl Baseline {
+    /// Labels become file names, so they may not leave the directory
+    pub fn path(dir: &Path, label: &str) -> Result<PathBuf, Error> {
+        if label.is_empty()
+            || label.starts_with('.')
+            || !label
+                .chars()
+                .all(|x| x.is_ascii_alphanumeric() || matches!(x, '-' | '_' | '.'))
+        {
+            bail!("invalid label {label:?}, use letters, digits, '-', '_' and '.'");
+        }
+        Ok(dir.join(format!("{label}.json")))
+    }
+
+    /// Loads a saved baseline, it's an error if nothing was saved under the label
+    pub fn load(dir: &Path, label: &str) -> Result<Self, Error> {
+        Self::load_or_default(dir, label)?
+            .with_context(|| format!("no results saved as {label} in {}", dir.display()))
+    }
+
+    fn load_or_default(dir: &Path, label: &str) -> Result<Option<Self>, Error> {
+        let path = Self::path(dir, label)?;
+        match std::fs::read_to_string(&path) {
+            Ok(data) => serde_json::from_str(&data)
+                .map(Some)
+                .with_context(|| format!("could not parse {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("could not read {}", path.display())),
+        }
+    }
+
+    /// The most recent result of every puzzle across all saved labels
+    pub fn latest(dir: &Path) -> Result<Self, Error> {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e).with_context(|| format!("could not read {}", dir.display())),
+        };
+        let mut saved = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|x| x == "json") {
+                saved.push((std::fs::metadata(&path)?.modified()?, path));
+            }
+        }
+        saved.sort();
+        let mut latest = Self::default();
+        for (_, path) in saved {
+            let data = std::fs::read_to_string(&path)
+                .with_context(|| format!("could not read {}", path.display()))?;
+            let baseline: Self = serde_json::from_str(&data)
+                .with_context(|| format!("could not parse {}", path.display()))?;
+            latest.results.extend(baseline.results);
+        }
+        Ok(latest)
+    }
+
+    /// Saves the results, merged into what is already stored under the label
+    pub fn save(&self, dir: &Path, label: &str) -> Result<(), Error> {
+        let mut merged = Self::load_or_default(dir, label)?.unwrap_or_default();
+        merged.results.extend(self.results.clone());
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("could not create {}", dir.display()))?;
+        let path = Self::path(dir, label)?;
+        std::fs::write(&path, serde_json::to_string_pretty(&merged)?)
+            .with_context(|| format!("could not write {}", path.display()))
+    }
+}
+
+/// The short hash of the checked out commit, to label results when no label is given
+pub fn commit_label() -> Option<String> {
+    let output = std::process::Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .ok()?;
+    let label = String::from_utf8(output.stdout).ok()?.trim().to_owned();
+    (output.status.success() && !label.is_empty()).then_some(label)
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{Baseline, BenchReport, Change, Stats};
+
+    #[test]
+    pub fn test_stats() {
+        let mut samples: Vec<_> = (10..=20).map(Duration::from_micros).collect();
+        samples.push(Duration::from_millis(5));
+        let stats = Stats::new(&samples);
+        assert_eq!(stats.samples, 12);
+        assert_eq!(stats.min, Duration::from_micros(10));
+        assert_eq!(stats.median, Duration::from_micros(16));
+        assert_eq!(stats.outliers, 1);
+        assert!(stats.mean > stats.median);
+    }
+
+    #[test]
+    pub fn test_compare() {
+        let stats = |mean: u64, stddev: u64| Stats {
+            samples: 1000,
+            min: Duration::from_micros(mean - 1),
+            median: Duration::from_micros(mean),
+            mean: Duration::from_micros(mean),
+            stddev: Duration::from_micros(stddev),
+            outliers: 0,
+        };
+        assert_eq!(stats(120, 5).compare(&stats(100, 5)), Change::Regression);
+        assert_eq!(stats(80, 5).compare(&stats(100, 5)), Change::Improvement);
+        assert_eq!(stats(101, 5).compare(&stats(100, 5)), Change::Unchanged);
+        assert_eq!(stats(120, 500).compare(&stats(100, 500)), Change::Unchanged);
+    }
+
+    #[test]
+    pub fn test_baseline_labels() {
+        let dir = tempfile::tempdir().unwrap();
+        let stats = Stats::new(&[Duration::from_micros(3)]);
+        let mut results = Baseline::default();
+        results.results.insert(
+            "2019.01.1".to_owned(),
+            BenchReport {
+                total: stats,
+                parse: stats,
+                unpack: stats,
+                solve: stats,
+                format: stats,
+                alloc: None,
+            },
+        );
+        results.save(dir.path(), "v1.0").unwrap();
+        let loaded = Baseline::load(dir.path(), "v1.0").unwrap();
+        assert_eq!(loaded.results["2019.01.1"].total.median, stats.median);
+        // a misspelled label must not compare against nothing
+        assert!(Baseline::load(dir.path(), "v1.1").is_err());
+        for label in ["", "../v1", "a/b", ".hidden"] {
+            assert!(results.save(dir.path(), label).is_err(), "{label}");
+        }
+    }
+}
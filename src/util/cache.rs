@@ -0,0 +1,534 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Error};
+use sha2::{Digest, Sha256};
+
+use crate::aoc::{day::Day, puzzlespec::PuzzleSpec, year::Year};
+
+/// Identifies a single file in the request cache
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum CacheKey {
+    Input(Year, Day),
+    Calendar(Year),
+    Puzzle(Year, Day),
+}
+
+impl std::fmt::Display for CacheKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CacheKey::Input(year, day) => f.write_fmt(format_args!("year{year}_day{day}_input")),
+            CacheKey::Calendar(year) => f.write_fmt(format_args!("year{year}_calendar")),
+            CacheKey::Puzzle(year, day) => f.write_fmt(format_args!("year{year}_day{day}_puzzle")),
+        }
+    }
+}
+
+impl FromStr for CacheKey {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rest = s
+            .strip_prefix("year")
+            .with_context(|| format!("not a cache entry: {s}"))?;
+        let (year, rest) = rest
+            .split_once('_')
+            .with_context(|| format!("not a cache entry: {s}"))?;
+        let year: Year = year.parse::<u16>()?.into();
+        if rest == "calendar" {
+            return Ok(Self::Calendar(year));
+        }
+        let (day, kind) = rest
+            .strip_prefix("day")
+            .and_then(|x| x.split_once('_'))
+            .with_context(|| format!("not a cache entry: {s}"))?;
+        let day: Day = day.parse::<u8>()?.into();
+        match kind {
+            "input" => Ok(Self::Input(year, day)),
+            "puzzle" => Ok(Self::Puzzle(year, day)),
+            _ => anyhow::bail!("not a cache entry: {s}"),
+        }
+    }
+}
+
+impl CacheKey {
+    pub fn year(&self) -> Year {
+        match self {
+            CacheKey::Input(year, _) | CacheKey::Puzzle(year, _) => *year,
+            CacheKey::Calendar(year) => *year,
+        }
+    }
+
+    /// The URL the data behind this key is fetched from
+    pub fn source_url(&self) -> String {
+        match self {
+            CacheKey::Input(year, day) => format!(
+                "https://adventofcode.com/{}/day/{}/input",
+                year.as_u16(),
+                day.as_u8()
+            ),
+            CacheKey::Calendar(year) => format!("https://adventofcode.com/{}", year.as_u16()),
+            CacheKey::Puzzle(year, day) => format!(
+                "https://adventofcode.com/{}/day/{}",
+                year.as_u16(),
+                day.as_u8()
+            ),
+        }
+    }
+
+    /// Calendars belong to a whole year, so they only match specs that don't name a day
+    pub fn matches(&self, spec: &PuzzleSpec) -> bool {
+        let year_eq = spec.year.map(|year| year == self.year()).unwrap_or(true);
+        let day_eq = match (self, spec.day) {
+            (_, None) => true,
+            (CacheKey::Input(_, day) | CacheKey::Puzzle(_, day), Some(spec_day)) => {
+                *day == spec_day
+            }
+            (CacheKey::Calendar(_), Some(_)) => false,
+        };
+        year_eq && day_eq
+    }
+}
+
+/// Stored next to every cache entry to detect truncated or otherwise damaged data
+#[derive(Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, Debug)]
+pub struct CacheMetadata {
+    /// Seconds since the unix epoch at which the data was fetched
+    fetched_at: u64,
+    source: String,
+    sha256: String,
+}
+
+impl CacheMetadata {
+    fn new(key: CacheKey, data: &str, fetched_at: SystemTime) -> Self {
+        Self {
+            fetched_at: fetched_at
+                .duration_since(UNIX_EPOCH)
+                .map(|x| x.as_secs())
+                .unwrap_or_default(),
+            source: key.source_url(),
+            sha256: checksum(data),
+        }
+    }
+    pub fn fetched_at(&self) -> SystemTime {
+        UNIX_EPOCH + std::time::Duration::from_secs(self.fetched_at)
+    }
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+    pub fn matches(&self, data: &str) -> bool {
+        self.sha256 == checksum(data)
+    }
+}
+
+fn checksum(data: &str) -> String {
+    Sha256::digest(data.as_bytes())
+        .iter()
+        .map(|x| format!("{x:02x}"))
+        .collect()
+}
+
+/// What was found when checking an entry against its sidecar
+enum Stored {
+    Missing,
+    Intact(String, CacheMetadata),
+    /// Written before entries had a sidecar, possibly truncated, or the sidecar wasn't renamed
+    /// into place yet
+    Unverified,
+    Corrupted,
+}
+
+#[derive(Clone, Debug)]
+pub struct CacheEntry {
+    key: CacheKey,
+    size: u64,
+    metadata: Option<CacheMetadata>,
+}
+
+impl CacheEntry {
+    pub fn key(&self) -> CacheKey {
+        self.key
+    }
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+    pub fn metadata(&self) -> Option<&CacheMetadata> {
+        self.metadata.as_ref()
+    }
+}
+
+/// File backed store for data requested from the AoC servers
+pub struct Cache {
+    root: PathBuf,
+}
+
+impl Cache {
+    /// The scratch directory of the build
+    pub fn default_root() -> PathBuf {
+        scratch::path("aoc_req")
+    }
+
+    pub fn new(root: PathBuf) -> Result<Self, Error> {
+        std::fs::create_dir_all(&root)
+            .with_context(|| format!("could not create cache directory {}", root.display()))?;
+        Ok(Self { root })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn path(&self, key: CacheKey) -> PathBuf {
+        self.root.join(key.to_string())
+    }
+
+    fn metadata_path(&self, key: CacheKey) -> PathBuf {
+        self.root.join(format!("{key}.meta"))
+    }
+
+    pub fn metadata(&self, key: CacheKey) -> Result<Option<CacheMetadata>, Error> {
+        Self::metadata_at(&self.metadata_path(key))
+    }
+
+    fn metadata_at(path: &Path) -> Result<Option<CacheMetadata>, Error> {
+        match std::fs::read_to_string(path) {
+            // a sidecar that doesn't parse is as good as a missing one
+            Ok(data) => Ok(serde_json::from_str(&data).ok()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("could not read {}", path.display())),
+        }
+    }
+
+    fn load(&self, key: CacheKey) -> Result<Stored, Error> {
+        let path = self.path(key);
+        let data = match std::fs::read_to_string(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Stored::Missing),
+            Err(e) if e.kind() == std::io::ErrorKind::InvalidData => return Ok(Stored::Corrupted),
+            Err(e) => return Err(e).with_context(|| format!("could not read cache entry {key}")),
+        };
+        match self.metadata(key)? {
+            Some(metadata) if metadata.matches(&data) => Ok(Stored::Intact(data, metadata)),
+            Some(_) => Ok(Stored::Corrupted),
+            None => Ok(Stored::Unverified),
+        }
+    }
+
+    /// Reads an entry, entries that fail their integrity check are removed and reported as missing.
+    /// Entries without a sidecar can't be checked, they are treated the same way.
+    pub fn read(&self, key: CacheKey) -> Result<Option<String>, Error> {
+        match self.load(key)? {
+            Stored::Missing => Ok(None),
+            Stored::Intact(data, _) => Ok(Some(data)),
+            Stored::Unverified => {
+                eprintln!("Cache entry {key} has no checksum, fetching it again");
+                self.remove(key)?;
+                Ok(None)
+            }
+            Stored::Corrupted => {
+                eprintln!("Cache entry {key} is corrupted, fetching it again");
+                self.remove(key)?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Replaces an entry and its metadata.
+    /// Both files are renamed into place on their own, the sidecar last. A reader in between sees
+    /// the new data with the old sidecar or none and removes the entry. That costs one more fetch
+    /// on the next read, never the data: the fetched data is returned by the caller either way.
+    pub fn write(&self, key: CacheKey, data: &str) -> Result<(), Error> {
+        self.write_atomic(&self.path(key), data)
+            .with_context(|| format!("could not write cache entry {key}"))?;
+        self.write_metadata(key, &CacheMetadata::new(key, data, SystemTime::now()))
+    }
+
+    fn write_metadata(&self, key: CacheKey, metadata: &CacheMetadata) -> Result<(), Error> {
+        let metadata =
+            serde_json::to_string_pretty(metadata).context("could not serialize cache metadata")?;
+        self.write_atomic(&self.metadata_path(key), &metadata)
+            .with_context(|| format!("could not write metadata of cache entry {key}"))
+    }
+
+    /// Writes into a temporary file in the cache directory and renames it over the target,
+    /// so readers either see the old or the new file but never a partial one
+    fn write_atomic(&self, path: &Path, data: &str) -> Result<(), Error> {
+        // unique across processes and the threads of this one
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let name = path
+            .file_name()
+            .and_then(|x| x.to_str())
+            .context("cache path without file name")?;
+        let tmp = self.root.join(format!(
+            ".{name}.{}.{}.tmp",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let mut file = std::fs::File::create(&tmp).context("could not create temporary file")?;
+        file.write_all(data.as_bytes())
+            .and_then(|_| file.sync_all())
+            .and_then(|_| std::fs::rename(&tmp, path))
+            .inspect_err(|_| {
+                let _ = std::fs::remove_file(&tmp);
+            })
+            .context("could not write temporary file")?;
+        Ok(())
+    }
+
+    /// Removes an entry, returns false if there was nothing to remove
+    pub fn remove(&self, key: CacheKey) -> Result<bool, Error> {
+        let _ = std::fs::remove_file(self.metadata_path(key));
+        match std::fs::remove_file(self.path(key)) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e).with_context(|| format!("could not remove cache entry {key}")),
+        }
+    }
+
+    /// Lists all entries in the cache, files that aren't cache entries are skipped
+    pub fn entries(&self) -> Result<Vec<CacheEntry>, Error> {
+        Self::entries_in(&self.root)
+    }
+
+    fn entries_in(dir: &Path) -> Result<Vec<CacheEntry>, Error> {
+        let mut entries = Vec::new();
+        for file in std::fs::read_dir(dir)
+            .with_context(|| format!("could not list directory {}", dir.display()))?
+        {
+            let file = file?;
+            let Some(key) = file
+                .file_name()
+                .to_str()
+                .and_then(|name| CacheKey::from_str(name).ok())
+            else {
+                continue;
+            };
+            entries.push(CacheEntry {
+                key,
+                size: file.metadata()?.len(),
+                metadata: Self::metadata_at(&dir.join(format!("{key}.meta")))?,
+            });
+        }
+        entries.sort_by_key(|x| x.key);
+        Ok(entries)
+    }
+
+    pub fn entries_matching(&self, spec: &PuzzleSpec) -> Result<Vec<CacheEntry>, Error> {
+        Ok(self
+            .entries()?
+            .into_iter()
+            .filter(|x| x.key.matches(spec))
+            .collect())
+    }
+
+    /// Removes all entries matching the spec and returns how many were removed
+    pub fn clear(&self, spec: &PuzzleSpec) -> Result<usize, Error> {
+        let mut removed = 0;
+        for entry in self.entries_matching(spec)? {
+            if self.remove(entry.key)? {
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Copies all intact entries and their metadata into `dir`, creating it if necessary.
+    /// Corrupted entries and those without a sidecar are skipped but left in the cache.
+    pub fn export(&self, dir: &Path) -> Result<usize, Error> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("could not create export directory {}", dir.display()))?;
+        let mut exported = 0;
+        for entry in self.entries()? {
+            let (data, metadata) = match self.load(entry.key)? {
+                Stored::Missing => continue,
+                Stored::Intact(data, metadata) => (data, metadata),
+                Stored::Unverified | Stored::Corrupted => {
+                    eprintln!("Skipping corrupted cache entry {}", entry.key);
+                    continue;
+                }
+            };
+            let metadata = serde_json::to_string_pretty(&metadata)?;
+            std::fs::write(dir.join(entry.key.to_string()), data)
+                .and_then(|_| std::fs::write(dir.join(format!("{}.meta", entry.key)), metadata))
+                .with_context(|| format!("could not export cache entry {}", entry.key))?;
+            exported += 1;
+        }
+        Ok(exported)
+    }
+
+    /// Copies all cache entries found in `dir` into the cache, replacing existing ones.
+    /// Entries are checked against their metadata and skipped if they don't match.
+    pub fn import(&self, dir: &Path) -> Result<usize, Error> {
+        let mut imported = 0;
+        for entry in Self::entries_in(dir)? {
+            let data = std::fs::read_to_string(dir.join(entry.key.to_string()))
+                .with_context(|| format!("could not import cache entry {}", entry.key))?;
+            match entry.metadata {
+                Some(metadata) if metadata.matches(&data) => {
+                    self.write_atomic(&self.path(entry.key), &data)?;
+                    self.write_metadata(entry.key, &metadata)?;
+                    imported += 1;
+                }
+                _ => eprintln!("Skipping corrupted cache entry {}", entry.key),
+            }
+        }
+        Ok(imported)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use clap::Parser;
+
+    use super::{Cache, CacheKey, CacheMetadata};
+    use crate::util::{Action, Arguments, CacheAction};
+
+    fn files(cache: &Cache) -> Vec<String> {
+        let mut files: Vec<_> = std::fs::read_dir(cache.root())
+            .unwrap()
+            .map(|x| x.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    pub fn test_cache_integrity() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path().join("cache")).unwrap();
+        let key = CacheKey::Input(2019.into(), 1.into());
+        cache.write(key, "12\n14\n").unwrap();
+        cache.write(key, "1969\n").unwrap();
+        // no temporary files are left behind
+        assert_eq!(
+            files(&cache),
+            ["year2019_day01_input", "year2019_day01_input.meta"]
+        );
+        assert_eq!(cache.read(key).unwrap().as_deref(), Some("1969\n"));
+
+        // truncated data doesn't match the sidecar, export leaves it alone but read removes it
+        std::fs::write(cache.path(key), "19").unwrap();
+        let export = dir.path().join("export");
+        assert_eq!(cache.export(&export).unwrap(), 0);
+        assert!(cache.path(key).exists());
+        assert_eq!(cache.read(key).unwrap(), None);
+        assert!(files(&cache).is_empty());
+
+        // entries without a sidecar, like the truncated ones of older versions, are fetched again
+        std::fs::write(cache.path(key), "19").unwrap();
+        assert_eq!(cache.metadata(key).unwrap(), None);
+        assert_eq!(cache.export(&export).unwrap(), 0);
+        assert_eq!(cache.read(key).unwrap(), None);
+        assert!(files(&cache).is_empty());
+
+        // a reader racing a refresh sees the new data with the old sidecar and removes the entry,
+        // the sidecar of the refresh then lands without data and the next fetch restores it
+        cache.write(key, "12\n").unwrap();
+        let sidecar = std::fs::read_to_string(cache.metadata_path(key)).unwrap();
+        std::fs::write(cache.path(key), "1969\n").unwrap();
+        assert_eq!(cache.read(key).unwrap(), None);
+        let new = CacheMetadata::new(key, "1969\n", std::time::SystemTime::now());
+        cache.write_metadata(key, &new).unwrap();
+        assert_eq!(cache.read(key).unwrap(), None);
+        cache.write(key, "1969\n").unwrap();
+        assert_eq!(cache.read(key).unwrap().as_deref(), Some("1969\n"));
+        assert_ne!(
+            std::fs::read_to_string(cache.metadata_path(key)).unwrap(),
+            sidecar
+        );
+    }
+
+    #[test]
+    pub fn test_cache_key() {
+        let keys = [
+            (
+                CacheKey::Input(2019.into(), 1.into()),
+                "year2019_day01_input",
+            ),
+            (
+                CacheKey::Puzzle(2023.into(), 25.into()),
+                "year2023_day25_puzzle",
+            ),
+            (CacheKey::Calendar(2015.into()), "year2015_calendar"),
+        ];
+        for (key, name) in keys {
+            assert_eq!(key.to_string(), name);
+            assert_eq!(name.parse::<CacheKey>().unwrap(), key);
+        }
+        for name in [
+            "year2019_day01_input.meta",
+            ".year2019_day01_input.1.0.tmp",
+            "year2019_day01",
+            "year2019_calendars",
+            "yearx_calendar",
+        ] {
+            assert!(name.parse::<CacheKey>().is_err(), "{name}");
+        }
+    }
+
+    #[test]
+    pub fn test_cache_clear_export_import() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path().join("cache")).unwrap();
+        let keys = [
+            CacheKey::Calendar(2019.into()),
+            CacheKey::Input(2019.into(), 1.into()),
+            CacheKey::Puzzle(2019.into(), 1.into()),
+            CacheKey::Input(2019.into(), 2.into()),
+            CacheKey::Input(2020.into(), 1.into()),
+        ];
+        for key in keys {
+            cache.write(key, &key.to_string()).unwrap();
+        }
+        std::fs::write(cache.root().join("notes.txt"), "not an entry").unwrap();
+        let listed = |cache: &Cache| {
+            cache
+                .entries()
+                .unwrap()
+                .iter()
+                .map(|x| x.key())
+                .collect::<Vec<_>>()
+        };
+        let mut sorted = keys;
+        sorted.sort();
+        assert_eq!(listed(&cache), sorted);
+        assert!(cache
+            .entries()
+            .unwrap()
+            .iter()
+            .all(|x| x.metadata().is_some()));
+
+        let export = dir.path().join("export");
+        assert_eq!(cache.export(&export).unwrap(), keys.len());
+
+        // the spec is parsed like on the command line, a day leaves the calendar alone
+        let spec = |args: &[&str]| match Arguments::parse_from(
+            ["aoc", "cache", "clear"].iter().chain(args),
+        )
+        .action()
+        {
+            Action::Cache(CacheAction::Clear { puzzle }) => puzzle.puzzle,
+            action => panic!("{action:?}"),
+        };
+        assert_eq!(cache.clear(&spec(&["2019.1"])).unwrap(), 2);
+        assert_eq!(listed(&cache), [keys[3], keys[4], keys[0]]);
+        assert_eq!(cache.clear(&spec(&["2019"])).unwrap(), 2);
+        assert_eq!(listed(&cache), [keys[4]]);
+        assert_eq!(cache.clear(&spec(&["*"])).unwrap(), 1);
+        assert!(listed(&cache).is_empty());
+
+        // importing restores the entries with their metadata
+        std::fs::write(export.join(keys[4].to_string()), "tampered").unwrap();
+        assert_eq!(cache.import(&export).unwrap(), keys.len() - 1);
+        assert_eq!(
+            cache.read(keys[1]).unwrap().as_deref(),
+            Some("year2019_day01_input")
+        );
+        assert_eq!(cache.read(keys[4]).unwrap(), None);
+    }
+}
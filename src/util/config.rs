@@ -0,0 +1,130 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Error};
+
+use crate::aoc::puzzlespec::PuzzleSpec;
+
+use super::output::OutputFormat;
+
+/// What to do with solutions of parts that aren't solved yet
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubmitPolicy {
+    /// Submit every solution
+    #[default]
+    Auto,
+    /// Ask before submitting a solution
+    Confirm,
+    /// Only print solutions
+    Never,
+}
+
+/// Runner defaults from `aoc.toml`, shared by everyone working in the repository.
+///
+/// ```toml
+/// spec = "2023"
+/// bench_loops = 100
+/// cache_dir = ".aoc-cache"
+/// profile = "alice"
+/// submit = "confirm"
+/// format = "table"
+/// ```
+///
+/// Flags and environment variables take precedence over the project-local `./aoc.toml`,
+/// which takes precedence over `aoc/aoc.toml` in the user config directory.
+/// A relative `cache_dir` is relative to the file that sets it.
+#[derive(Clone, Default, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Puzzles to work on when no spec is given
+    #[serde(default, deserialize_with = "deserialize_spec")]
+    pub spec: Option<PuzzleSpec>,
+    pub bench_loops: Option<u32>,
+    pub cache_dir: Option<PathBuf>,
+    pub profile: Option<String>,
+    pub submit: Option<SubmitPolicy>,
+    pub format: Option<OutputFormat>,
+}
+
+fn deserialize_spec<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<PuzzleSpec>, D::Error> {
+    let spec = <Option<String> as serde::Deserialize>::deserialize(deserializer)?;
+    spec.map(|x| x.parse().map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+impl Config {
+    /// `./aoc.toml` merged over the user level `aoc/aoc.toml`
+    pub fn load() -> Result<Self, Error> {
+        let user = match dirs::config_dir() {
+            Some(dir) => Self::load_from(&dir.join("aoc").join("aoc.toml"))?,
+            None => Self::default(),
+        };
+        Ok(Self::load_from(Path::new("aoc.toml"))?.or(user))
+    }
+
+    /// Loads a single config file, a missing file is an empty config
+    pub fn load_from(path: &Path) -> Result<Self, Error> {
+        let data = match std::fs::read_to_string(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e).with_context(|| format!("could not read {}", path.display())),
+        };
+        let mut config: Self =
+            toml::from_str(&data).with_context(|| format!("could not parse {}", path.display()))?;
+        if let Some(dir) = path.parent() {
+            config.cache_dir = config.cache_dir.map(|x| dir.join(x));
+        }
+        Ok(config)
+    }
+
+    /// Takes every setting missing here from `other`
+    pub fn or(self, other: Self) -> Self {
+        Self {
+            spec: self.spec.or(other.spec),
+            bench_loops: self.bench_loops.or(other.bench_loops),
+            cache_dir: self.cache_dir.or(other.cache_dir),
+            profile: self.profile.or(other.profile),
+            submit: self.submit.or(other.submit),
+            format: self.format.or(other.format),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Config, SubmitPolicy};
+    use crate::aoc::{puzzlespec::PuzzleSpec, year::year};
+
+    #[test]
+    pub fn test_config_precedence() {
+        let project: Config = toml::from_str("spec = \"2023\"\nsubmit = \"never\"").unwrap();
+        let user: Config = toml::from_str("spec = \"2019.1\"\nbench_loops = 10").unwrap();
+        let config = project.or(user);
+        assert_eq!(
+            config.spec,
+            Some(PuzzleSpec {
+                year: Some(year(2023)),
+                day: None,
+                part: None
+            })
+        );
+        assert_eq!(config.bench_loops, Some(10));
+        assert_eq!(config.submit, Some(SubmitPolicy::Never));
+        assert!(toml::from_str::<Config>("bench_loop = 10").is_err());
+    }
+
+    #[test]
+    pub fn test_config_relative_cache_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("aoc.toml");
+        std::fs::write(&path, "cache_dir = \".aoc-cache\"").unwrap();
+        let config = Config::load_from(&path).unwrap();
+        assert_eq!(config.cache_dir, Some(dir.path().join(".aoc-cache")));
+        let absolute = dir.path().join("elsewhere");
+        std::fs::write(&path, format!("cache_dir = '{}'", absolute.display())).unwrap();
+        let config = Config::load_from(&path).unwrap();
+        assert_eq!(config.cache_dir, Some(absolute));
+    }
+}
@@ -0,0 +1,206 @@
+use std::time::Duration;
+
+use crate::aoc::{
+    day::Day, get_puzzle, part::Part, part_status::PartStatus, progress_report::ProgressReport,
+    year::Year, SelfTestResult,
+};
+
+use super::{
+    bench::Baseline,
+    output::{nanos_opt, Record},
+};
+
+/// How the self-tests of a part went
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TestStatus {
+    NoTests,
+    Passed(usize),
+    Failed { failed: usize, total: usize },
+}
+
+/// Flattened into the part as `tests` and `tests_failed`
+impl serde::Serialize for TestStatus {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let (total, failed) = match *self {
+            TestStatus::NoTests => (0, 0),
+            TestStatus::Passed(total) => (total, 0),
+            TestStatus::Failed { failed, total } => (total, failed),
+        };
+        let mut s = s.serialize_struct("TestStatus", 2)?;
+        s.serialize_field("tests", &total)?;
+        s.serialize_field("tests_failed", &failed)?;
+        s.end()
+    }
+}
+
+impl std::fmt::Display for TestStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TestStatus::NoTests => f.write_str("-"),
+            TestStatus::Passed(total) => write!(f, "{total} ok"),
+            TestStatus::Failed { failed, total } => write!(f, "{failed}/{total} FAIL"),
+        }
+    }
+}
+
+/// Everything known about one part of a day
+#[derive(Clone, Copy, PartialEq, Debug, serde::Serialize)]
+pub struct PartOverview {
+    pub status: PartStatus,
+    /// Whether a solution is registered for the part
+    pub solution: bool,
+    #[serde(flatten)]
+    pub tests: TestStatus,
+    /// Median of the most recently saved benchmark
+    #[serde(rename = "bench_ns", serialize_with = "nanos_opt")]
+    pub bench: Option<Duration>,
+}
+
+impl PartOverview {
+    fn new(progress: ProgressReport, tests: &[SelfTestResult], benches: &Baseline) -> PartOverview {
+        let puzzle = get_puzzle(progress.year(), progress.day(), progress.part());
+        let tests: Vec<_> = tests
+            .iter()
+            .filter(|x| {
+                x.puzzle().year() == progress.year()
+                    && x.puzzle().day() == progress.day()
+                    && x.puzzle().part() == progress.part()
+            })
+            .collect();
+        let failed = tests.iter().filter(|x| !x.passed()).count();
+        PartOverview {
+            status: progress.status(),
+            solution: puzzle.is_some(),
+            tests: match (tests.len(), failed) {
+                (0, _) => TestStatus::NoTests,
+                (total, 0) => TestStatus::Passed(total),
+                (total, failed) => TestStatus::Failed { failed, total },
+            },
+            bench: puzzle
+                .and_then(|x| benches.results.get(&x.to_string()))
+                .map(|x| x.total.median),
+        }
+    }
+
+    /// Solved locally but not on the server yet
+    pub fn ready(&self) -> bool {
+        self.status == PartStatus::MissingWithSolution
+    }
+
+    fn cells(&self) -> [String; 4] {
+        let status = match self.status {
+            PartStatus::Completed => "**",
+            PartStatus::MissingWithSolution => "READY",
+            PartStatus::MissingWithNoSolution => "todo",
+            PartStatus::Unavailable => "-",
+            PartStatus::Locked => "locked",
+        };
+        [
+            status.to_owned(),
+            if self.solution { "yes" } else { "-" }.to_owned(),
+            self.tests.to_string(),
+            self.bench
+                .map(|x| format!("{x:.2?}"))
+                .unwrap_or_else(|| "-".to_owned()),
+        ]
+    }
+}
+
+/// One row of the year overview.
+/// JSON: `year`, `day` and `parts`, both with `status`, `solution`, the number of `tests` and
+/// `tests_failed`, and the `bench_ns` median.
+#[derive(Clone, Copy, PartialEq, Debug, serde::Serialize)]
+pub struct DayOverview {
+    pub year: Year,
+    pub day: Day,
+    pub parts: [PartOverview; 2],
+}
+
+impl DayOverview {
+    /// Combines the progress of both parts with the self-test results and saved benchmarks
+    pub fn new(
+        progress: [ProgressReport; 2],
+        tests: &[SelfTestResult],
+        benches: &Baseline,
+    ) -> DayOverview {
+        DayOverview {
+            year: progress[0].year(),
+            day: progress[0].day(),
+            parts: progress.map(|x| PartOverview::new(x, tests, benches)),
+        }
+    }
+
+    /// The parts that can be submitted right away
+    pub fn ready(&self) -> impl Iterator<Item = Part> + '_ {
+        [Part::one(), Part::two()]
+            .into_iter()
+            .zip(&self.parts)
+            .filter(|(_, x)| x.ready())
+            .map(|(part, _)| part)
+    }
+}
+
+impl Record for DayOverview {
+    const COLUMNS: &'static [&'static str] = &[
+        "day", "part 1", "code", "tests", "bench", "part 2", "code", "tests", "bench",
+    ];
+
+    fn cells(&self) -> Vec<String> {
+        std::iter::once(self.day.to_string())
+            .chain(self.parts.iter().flat_map(PartOverview::cells))
+            .collect()
+    }
+
+    fn plain(&self) -> String {
+        self.cells().join(" ")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{DayOverview, TestStatus};
+    use crate::{
+        aoc::{part_status::PartStatus, progress_report::ProgressReport, run_self_test},
+        util::{
+            bench::{Baseline, BenchReport, Stats},
+            sandbox::Limits,
+        },
+    };
+
+    #[test]
+    pub fn test_day_overview() {
+        let tests = run_self_test(Some("2019.1".parse().unwrap()), false, 1, Limits::default());
+        let stats = Stats {
+            median: Duration::from_micros(3),
+            ..Stats::default()
+        };
+        let mut benches = Baseline::default();
+        benches.results.insert(
+            "2019.01.2".to_owned(),
+            BenchReport {
+                total: stats,
+                parse: stats,
+                unpack: stats,
+                solve: stats,
+                format: stats,
+                alloc: None,
+            },
+        );
+        let overview = DayOverview::new(
+            [
+                ProgressReport::new(2019u16, 1u8, 1u8, PartStatus::Completed),
+                ProgressReport::new(2019u16, 1u8, 2u8, PartStatus::MissingWithSolution),
+            ],
+            &tests,
+            &benches,
+        );
+        assert!(overview.parts.iter().all(|x| x.solution));
+        assert!(matches!(overview.parts[0].tests, TestStatus::Passed(_)));
+        assert_eq!(overview.parts[0].bench, None);
+        assert_eq!(overview.parts[1].bench, Some(Duration::from_micros(3)));
+        assert_eq!(overview.ready().map(|x| x.as_u8()).collect::<Vec<_>>(), [2]);
+    }
+}
@@ -0,0 +1,221 @@
+use scraper::{node::Node, ElementRef, Html};
+
+/// Renders a puzzle description as plain terminal text wrapped at `width`
+pub fn to_text(html: &str, width: usize) -> String {
+    html2text::from_read_with_decorator(
+        html.as_bytes(),
+        width,
+        html2text::render::text_renderer::TrivialDecorator::new(),
+    )
+}
+
+/// Renders a puzzle description as Markdown.
+///
+/// Descriptions only use a handful of elements: headings, paragraphs, lists, links, `<pre><code>`
+/// blocks that become fenced code blocks and `<code>` and `<em>` within the text. An answer
+/// emphasised within code, like `<code><em>142</em></code>`, becomes ``*`142`*``.
+pub fn to_markdown(html: &str) -> String {
+    let html = Html::parse_fragment(html);
+    let mut blocks = Vec::new();
+    block(html.root_element(), 0, &mut blocks);
+    blocks.join("\n\n") + "\n"
+}
+
+fn is_block(name: &str) -> bool {
+    matches!(
+        name,
+        "article" | "main" | "div" | "p" | "pre" | "ul" | "ol" | "h1" | "h2" | "h3" | "h4"
+    )
+}
+
+/// Appends the blocks of `element`, text and inline elements between blocks form paragraphs
+fn block(element: ElementRef, indent: usize, blocks: &mut Vec<String>) {
+    let mut paragraph = String::new();
+    let flush = |paragraph: &mut String, blocks: &mut Vec<String>| {
+        let text = paragraph.trim();
+        if !text.is_empty() {
+            blocks.push(format!("{}{text}", " ".repeat(indent)));
+        }
+        paragraph.clear();
+    };
+    for child in element.children() {
+        let Some(child) = ElementRef::wrap(child) else {
+            if let Node::Text(text) = child.value() {
+                paragraph.push_str(&escape(&collapse(text)));
+            }
+            continue;
+        };
+        let name = child.value().name();
+        if !is_block(name) {
+            paragraph.push_str(&inline_element(child));
+            continue;
+        }
+        flush(&mut paragraph, blocks);
+        match name {
+            "h1" | "h2" | "h3" | "h4" => {
+                let level = name[1..].parse().unwrap_or(2);
+                blocks.push(format!("{} {}", "#".repeat(level), inline(child).trim()));
+            }
+            "p" => blocks.push(format!("{}{}", " ".repeat(indent), inline(child).trim())),
+            "pre" => {
+                let code = child.text().collect::<String>();
+                let fence = "`".repeat(longest_run(&code, '`').max(2) + 1);
+                let code = code.trim_end_matches('\n');
+                blocks.push(format!("{fence}\n{code}\n{fence}"));
+            }
+            "ul" | "ol" => blocks.push(list(child, indent, name == "ol")),
+            _ => block(child, indent, blocks),
+        }
+    }
+    flush(&mut paragraph, blocks);
+}
+
+fn list(element: ElementRef, indent: usize, ordered: bool) -> String {
+    let mut items = Vec::new();
+    for item in element.children().filter_map(ElementRef::wrap) {
+        let marker = if ordered {
+            format!("{}.", items.len() + 1)
+        } else {
+            "-".to_owned()
+        };
+        let mut nested = Vec::new();
+        block(item, indent + marker.len() + 1, &mut nested);
+        let item = nested.join("\n");
+        items.push(format!(
+            "{}{marker} {}",
+            " ".repeat(indent),
+            item.trim_start()
+        ));
+    }
+    items.join("\n")
+}
+
+/// The contents of `element` as inline Markdown
+fn inline(element: ElementRef) -> String {
+    let mut text = String::new();
+    for child in element.children() {
+        match ElementRef::wrap(child) {
+            Some(child) => text.push_str(&inline_element(child)),
+            None => {
+                if let Node::Text(content) = child.value() {
+                    text.push_str(&escape(&collapse(content)));
+                }
+            }
+        }
+    }
+    text
+}
+
+fn inline_element(element: ElementRef) -> String {
+    let content = inline(element);
+    match element.value().name() {
+        "em" | "i" => wrap(&content, "*"),
+        "strong" | "b" => wrap(&content, "**"),
+        "code" => {
+            let code = collapse(&element.text().collect::<String>());
+            let emphasised = element
+                .children()
+                .filter_map(ElementRef::wrap)
+                .any(|x| x.value().name() == "em" && x.text().collect::<String>() == code);
+            if emphasised {
+                format!("*{}*", code_span(&code))
+            } else {
+                code_span(&code)
+            }
+        }
+        "a" => match element.value().attr("href") {
+            Some(href) if href.starts_with('/') => {
+                format!("[{content}](https://adventofcode.com{href})")
+            }
+            Some(href) => format!("[{content}]({href})"),
+            None => content,
+        },
+        "br" => "  \n".to_owned(),
+        _ => content,
+    }
+}
+
+/// Emphasis markers have to touch the text, so surrounding whitespace is moved outside
+fn wrap(content: &str, marker: &str) -> String {
+    let trimmed = content.trim();
+    if trimmed.is_empty() {
+        return content.to_owned();
+    }
+    let start = &content[..content.len() - content.trim_start().len()];
+    let end = &content[content.trim_end().len()..];
+    format!("{start}{marker}{trimmed}{marker}{end}")
+}
+
+fn code_span(code: &str) -> String {
+    let ticks = "`".repeat(longest_run(code, '`') + 1);
+    if code.contains('`') {
+        format!("{ticks} {code} {ticks}")
+    } else {
+        format!("{ticks}{code}{ticks}")
+    }
+}
+
+fn longest_run(text: &str, c: char) -> usize {
+    text.split(|x| x != c).map(str::len).max().unwrap_or(0)
+}
+
+/// Whitespace in HTML text collapses into single spaces
+fn collapse(text: &str) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    let mut space = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            space = true;
+            continue;
+        }
+        if space {
+            collapsed.push(' ');
+            space = false;
+        }
+        collapsed.push(c);
+    }
+    if space {
+        collapsed.push(' ');
+    }
+    collapsed
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::{to_markdown, to_text};
+
+    #[test]
+    pub fn test_render_description() {
+        let html = include_str!("../../fixtures/puzzle/2023_day01.html");
+        let markdown = to_markdown(html);
+        assert!(markdown.starts_with("## --- Day 1: Trebuchet?! ---\n\nThe newly-improved"));
+        assert!(markdown.contains("a specific *calibration value* that"));
+        assert!(markdown.contains("\n\n```\n1abc2\npqr3stu8vwx\na1b2c3d4e5f\ntreb7uchet\n```\n\n"));
+        assert!(markdown
+            .contains("are `12`, `38`, `15`, and `77`. Adding these together produces *`142`*."));
+        assert!(markdown.contains("\n\nYour puzzle answer was `54390`.\n\n## --- Part Two ---"));
+        assert!(!markdown.contains('<'));
+
+        // emphasis within a part of the code is lost, the code isn't
+        let markdown = to_markdown(include_str!("../../fixtures/puzzle/2019_day02.html"));
+        assert!(markdown.contains("\n- `1,0,0,0,99` becomes `2,0,0,0,99` (`1 + 1 = 2`).\n- "));
+
+        let text = to_text(html, 80);
+        assert!(text.contains("--- Day 1: Trebuchet?! ---"));
+        assert!(text.contains("\n1abc2\npqr3stu8vwx\n"));
+        assert!(text.contains("produces 142."));
+        assert!(!text.contains('`') && !text.contains('*'));
+        assert!(text.lines().all(|x| x.chars().count() <= 80));
+    }
+}
@@ -0,0 +1,142 @@
+use crate::aoc::part::Part;
+
+/// An example found in a puzzle description, to be reviewed before it becomes a self-test
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ExampleCandidate {
+    part: Part,
+    input: String,
+    /// The emphasised answer of the example, if the description has a clear one
+    output: Option<String>,
+}
+
+impl ExampleCandidate {
+    pub fn part(&self) -> Part {
+        self.part
+    }
+
+    /// Renders the candidate as an `add_test!` invocation for the solution type `sol`
+    pub fn to_add_test(&self, sol: &str) -> String {
+        let hashes = (0..)
+            .map(|n| "#".repeat(n))
+            .find(|hashes| !self.input.contains(&format!("\"{hashes}")))
+            .unwrap();
+        let line = format!(
+            "add_test!({sol}, part {}, r{hashes}\"{}\"{hashes} => {:?});",
+            self.part,
+            self.input,
+            self.output.as_deref().unwrap_or("TODO"),
+        );
+        if self.output.is_some() {
+            line
+        } else {
+            format!(
+                "// no answer found in the description\n// {}",
+                line.replace('\n', "\n// ")
+            )
+        }
+    }
+}
+
+/// Finds the example of every part in a puzzle description.
+///
+/// Every `article.day-desc` is one part. Its first `<pre><code>` block is taken as the example
+/// input and the last `<code><em>` as the answer, which is how the descriptions usually highlight
+/// the result of the example. Parts without an example block reuse the input of the previous part.
+pub fn extract_examples(html: &str) -> Vec<ExampleCandidate> {
+    let html = scraper::Html::parse_fragment(html);
+    let article = scraper::Selector::parse("article.day-desc").unwrap();
+    let block = scraper::Selector::parse("pre > code").unwrap();
+    let answer = scraper::Selector::parse("code > em").unwrap();
+
+    let mut candidates: Vec<ExampleCandidate> = Vec::new();
+    for (part, article) in html.select(&article).enumerate() {
+        let input = article
+            .select(&block)
+            .next()
+            .map(|x| x.text().collect::<String>())
+            .map(|x| x.trim_end_matches('\n').to_owned())
+            .or_else(|| candidates.last().map(|x| x.input.clone()));
+        let Some(input) = input else {
+            continue;
+        };
+        // only emphasis covering the whole code element is an answer, not highlighted parts of it
+        let output = article
+            .select(&answer)
+            .filter(|em| {
+                em.parent()
+                    .and_then(scraper::ElementRef::wrap)
+                    .map(|code| code.text().collect::<String>() == em.text().collect::<String>())
+                    .unwrap_or(false)
+            })
+            .last()
+            .map(|em| em.text().collect::<String>());
+        candidates.push(ExampleCandidate {
+            part: (part as u8 + 1).into(),
+            input,
+            output,
+        });
+    }
+    candidates
+}
+
+/// Finds the answers accepted for each part, which the description shows once a part is solved
+pub fn submitted_answers(html: &str) -> Vec<(Part, String)> {
+    let html = scraper::Html::parse_fragment(html);
+    let paragraph = scraper::Selector::parse("p").unwrap();
+    let code = scraper::Selector::parse("code").unwrap();
+    html.select(&paragraph)
+        .filter(|p| {
+            p.text()
+                .collect::<String>()
+                .starts_with("Your puzzle answer was")
+        })
+        .filter_map(|p| p.select(&code).next())
+        .enumerate()
+        .map(|(part, code)| ((part as u8 + 1).into(), code.text().collect()))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::aoc::part::Part;
+
+    use super::{extract_examples, submitted_answers};
+
+    #[test]
+    pub fn test_examples_2023_day1() {
+        let examples = extract_examples(include_str!("../../fixtures/puzzle/2023_day01.html"));
+        assert_eq!(examples.len(), 2);
+        assert_eq!(
+            examples[0].input,
+            "1abc2\npqr3stu8vwx\na1b2c3d4e5f\ntreb7uchet"
+        );
+        assert_eq!(examples[0].output.as_deref(), Some("142"));
+        assert!(examples[1].input.starts_with("two1nine\n"));
+        assert_eq!(examples[1].output.as_deref(), Some("281"));
+        assert_eq!(
+            examples[0].to_add_test("S"),
+            "add_test!(S, part 1, r\"1abc2\npqr3stu8vwx\na1b2c3d4e5f\ntreb7uchet\" => \"142\");"
+        );
+    }
+
+    #[test]
+    pub fn test_examples_without_answer() {
+        let examples = extract_examples(include_str!("../../fixtures/puzzle/2019_day02.html"));
+        assert_eq!(examples.len(), 1);
+        assert_eq!(examples[0].input, "1,9,10,3,2,3,11,0,99,30,40,50");
+        assert_eq!(examples[0].output.as_deref(), None);
+        assert!(examples[0].to_add_test("S").starts_with("// "));
+    }
+
+    #[test]
+    pub fn test_submitted_answers() {
+        let answers = submitted_answers(include_str!("../../fixtures/puzzle/2023_day01.html"));
+        assert_eq!(
+            answers,
+            vec![
+                (Part::one(), "54390".to_owned()),
+                (Part::two(), "54277".to_owned())
+            ]
+        );
+    }
+}
@@ -0,0 +1,874 @@
+//! Records printed by the actions, in every [`OutputFormat`].
+//!
+//! The JSON format prints one flat object per line. Keys are snake_case, enum values are
+//! snake_case strings, durations are integer nanoseconds in keys ending in `_ns`, and values
+//! that don't apply are `null`. Messages meant for humans go to stderr in every format.
+
+use std::{io::Write, path::PathBuf, time::Duration};
+
+use anyhow::Error;
+use serde::Serializer;
+
+use crate::aoc::{
+    day::Day, part::Part, progress_report::ProgressReport, year::Year, Puzzle, SelfTestOutcome,
+    SelfTestResult,
+};
+
+use super::{
+    bench::{BenchReport, Change, Stats},
+    cache::CacheEntry,
+    sandbox::Outcome,
+    SubmissionOutcome,
+};
+
+/// How results of the actions are printed
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// One human readable line per result
+    #[default]
+    Plain,
+    /// An aligned table once all results are known
+    Table,
+    /// One JSON object per result and line
+    Json,
+}
+
+/// A single result of an action, printable in every [`OutputFormat`]
+pub trait Record: serde::Serialize {
+    const COLUMNS: &'static [&'static str];
+    fn cells(&self) -> Vec<String>;
+    /// The text printed in the plain format
+    fn plain(&self) -> String;
+}
+
+/// Prints plain and JSON records right away, tables are printed by [`Printer::finish`]
+pub struct Printer<R: Record, W: Write = std::io::Stdout> {
+    format: OutputFormat,
+    out: W,
+    rows: Vec<Vec<String>>,
+    _record: std::marker::PhantomData<R>,
+}
+
+impl<R: Record> Printer<R> {
+    pub fn new(format: OutputFormat) -> Self {
+        Self::with_writer(format, std::io::stdout())
+    }
+}
+
+impl<R: Record, W: Write> Printer<R, W> {
+    pub fn with_writer(format: OutputFormat, out: W) -> Self {
+        Self {
+            format,
+            out,
+            rows: Vec::new(),
+            _record: std::marker::PhantomData,
+        }
+    }
+
+    pub fn print(&mut self, record: &R) -> Result<(), Error> {
+        match self.format {
+            OutputFormat::Plain => writeln!(self.out, "{}", record.plain())?,
+            OutputFormat::Table => self.rows.push(record.cells()),
+            OutputFormat::Json => writeln!(self.out, "{}", serde_json::to_string(record)?)?,
+        }
+        Ok(())
+    }
+
+    /// Prints the table if there is one and hands back the writer
+    pub fn finish(mut self) -> Result<W, Error> {
+        if self.format != OutputFormat::Table {
+            return Ok(self.out);
+        }
+        let widths: Vec<usize> = R::COLUMNS
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                self.rows
+                    .iter()
+                    .filter_map(|row| row.get(i))
+                    .map(|x| x.chars().count())
+                    .chain([column.len()])
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect();
+        let mut line = |cells: Vec<&str>| {
+            let line: Vec<String> = cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:<width$}"))
+                .collect();
+            writeln!(self.out, "{}", line.join("  ").trim_end())
+        };
+        line(R::COLUMNS.to_vec())?;
+        for row in &self.rows {
+            line(row.iter().map(String::as_str).collect())?;
+        }
+        Ok(self.out)
+    }
+}
+
+fn duration(x: Duration) -> String {
+    format!("{x:.2?}")
+}
+
+/// Serializes a duration as integer nanoseconds
+pub fn nanos<S: Serializer>(x: &Duration, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_u64(x.as_nanos().try_into().unwrap_or(u64::MAX))
+}
+
+/// Serializes an optional duration as integer nanoseconds or `null`
+pub fn nanos_opt<S: Serializer>(x: &Option<Duration>, s: S) -> Result<S::Ok, S::Error> {
+    match x {
+        Some(x) => nanos(x, s),
+        None => s.serialize_none(),
+    }
+}
+
+/// `year`, `day`, `part` and `status`
+impl Record for ProgressReport {
+    const COLUMNS: &'static [&'static str] = &["year", "day", "part", "status"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.year().to_string(),
+            self.day().to_string(),
+            self.part().to_string(),
+            self.status().to_string(),
+        ]
+    }
+
+    fn plain(&self) -> String {
+        format!(
+            "Year {}, Day {}, Part {} = {}",
+            self.year(),
+            self.day(),
+            self.part(),
+            self.status()
+        )
+    }
+}
+
+/// How a call ended, the flat JSON form of an [`Outcome`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutcomeStatus {
+    Solved,
+    Error,
+    Panicked,
+    TimedOut,
+    Crashed,
+}
+
+/// The fields an [`Outcome`] adds to a JSON record: `status`, `answer` and `error`, and the
+/// phases `parse_ns`, `unpack_ns`, `solve_ns`, `format_ns` and `total_ns` of solved calls
+#[derive(serde::Serialize)]
+struct OutcomeJson<'a> {
+    status: OutcomeStatus,
+    answer: Option<&'a str>,
+    error: Option<String>,
+    #[serde(serialize_with = "nanos_opt")]
+    parse_ns: Option<Duration>,
+    #[serde(serialize_with = "nanos_opt")]
+    unpack_ns: Option<Duration>,
+    #[serde(serialize_with = "nanos_opt")]
+    solve_ns: Option<Duration>,
+    #[serde(serialize_with = "nanos_opt")]
+    format_ns: Option<Duration>,
+    #[serde(serialize_with = "nanos_opt")]
+    total_ns: Option<Duration>,
+}
+
+impl<'a> From<&'a Outcome> for OutcomeJson<'a> {
+    fn from(outcome: &'a Outcome) -> Self {
+        let (status, error) = match outcome {
+            Outcome::Solved(..) => (OutcomeStatus::Solved, None),
+            Outcome::Error(e) => (OutcomeStatus::Error, Some(e.clone())),
+            Outcome::Panicked => (OutcomeStatus::Panicked, None),
+            Outcome::TimedOut(timeout) => (
+                OutcomeStatus::TimedOut,
+                Some(format!("timed out after {timeout:?}")),
+            ),
+            Outcome::Crashed(status) => (OutcomeStatus::Crashed, Some(status.clone())),
+        };
+        let (answer, timings) = match outcome {
+            Outcome::Solved(answer, timings) => (Some(answer.as_str()), Some(timings)),
+            _ => (None, None),
+        };
+        Self {
+            status,
+            answer,
+            error,
+            parse_ns: timings.map(|x| x.parse),
+            unpack_ns: timings.map(|x| x.unpack),
+            solve_ns: timings.map(|x| x.solve),
+            format_ns: timings.map(|x| x.format),
+            total_ns: timings.map(|x| x.total()),
+        }
+    }
+}
+
+/// Result of running and possibly submitting one puzzle.
+/// JSON: `year`, `day`, `part`, the [`OutcomeJson`] fields and `submission`.
+#[derive(Clone, Debug)]
+pub struct RunRecord {
+    year: Year,
+    day: Day,
+    part: Part,
+    outcome: Outcome,
+    submission: Option<SubmissionOutcome>,
+}
+
+impl RunRecord {
+    pub fn new(puzzle: &Puzzle, outcome: Outcome, submission: Option<SubmissionOutcome>) -> Self {
+        Self {
+            year: puzzle.year(),
+            day: puzzle.day(),
+            part: puzzle.part(),
+            outcome,
+            submission,
+        }
+    }
+
+    fn puzzle(&self) -> String {
+        format!("{}.{}.{}", self.year, self.day, self.part)
+    }
+}
+
+impl serde::Serialize for RunRecord {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        #[derive(serde::Serialize)]
+        struct Json<'a> {
+            year: Year,
+            day: Day,
+            part: Part,
+            #[serde(flatten)]
+            outcome: OutcomeJson<'a>,
+            submission: Option<SubmissionOutcome>,
+        }
+        Json {
+            year: self.year,
+            day: self.day,
+            part: self.part,
+            outcome: (&self.outcome).into(),
+            submission: self.submission,
+        }
+        .serialize(s)
+    }
+}
+
+impl Record for RunRecord {
+    const COLUMNS: &'static [&'static str] = &["puzzle", "answer", "time", "submission"];
+
+    fn cells(&self) -> Vec<String> {
+        let (answer, time) = match &self.outcome {
+            Outcome::Solved(answer, timings) => (answer.clone(), duration(timings.total())),
+            Outcome::Error(_) => ("error".to_owned(), String::new()),
+            Outcome::Panicked => ("panicked".to_owned(), String::new()),
+            Outcome::TimedOut(timeout) => ("timed out".to_owned(), duration(*timeout)),
+            Outcome::Crashed(_) => ("crashed".to_owned(), String::new()),
+        };
+        let submission = self
+            .submission
+            .map(|x| format!("{x:?}"))
+            .unwrap_or_default();
+        vec![self.puzzle(), answer, time, submission]
+    }
+
+    fn plain(&self) -> String {
+        let puzzle = self.puzzle();
+        let line = match &self.outcome {
+            Outcome::Solved(solution, timings) => {
+                format!("Solution for {puzzle} = {solution} ({timings})")
+            }
+            Outcome::Error(e) => format!("Puzzle {puzzle} encountered an error: {e}"),
+            Outcome::Panicked => format!("Puzzle {puzzle} panicked"),
+            Outcome::TimedOut(timeout) => format!("Puzzle {puzzle} timed out after {timeout:?}"),
+            Outcome::Crashed(status) => {
+                format!("Puzzle {puzzle} crashed ({status}), it may have run out of memory")
+            }
+        };
+        match self.submission {
+            Some(submission) => format!("{line}\nSubmission Result: {submission:?}"),
+            None => line,
+        }
+    }
+}
+
+/// Result of running a puzzle on the input of another profile.
+/// JSON: `year`, `day`, `part`, `profile`, the [`OutcomeJson`] fields, the `accepted` answer
+/// and `matches`, which is `null` when nothing was accepted or the call failed.
+#[derive(Clone, Debug)]
+pub struct CrossCheckRecord {
+    year: Year,
+    day: Day,
+    part: Part,
+    profile: String,
+    outcome: Outcome,
+    accepted: Option<String>,
+}
+
+impl CrossCheckRecord {
+    pub fn new(puzzle: &Puzzle, profile: &str, outcome: Outcome, accepted: Option<String>) -> Self {
+        Self {
+            year: puzzle.year(),
+            day: puzzle.day(),
+            part: puzzle.part(),
+            profile: profile.to_owned(),
+            outcome,
+            accepted,
+        }
+    }
+
+    fn matches(&self) -> Option<bool> {
+        match (&self.outcome, &self.accepted) {
+            (Outcome::Solved(solution, _), Some(accepted)) => Some(solution == accepted),
+            _ => None,
+        }
+    }
+}
+
+impl serde::Serialize for CrossCheckRecord {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        #[derive(serde::Serialize)]
+        struct Json<'a> {
+            year: Year,
+            day: Day,
+            part: Part,
+            profile: &'a str,
+            #[serde(flatten)]
+            outcome: OutcomeJson<'a>,
+            accepted: Option<&'a str>,
+            matches: Option<bool>,
+        }
+        Json {
+            year: self.year,
+            day: self.day,
+            part: self.part,
+            profile: &self.profile,
+            outcome: (&self.outcome).into(),
+            accepted: self.accepted.as_deref(),
+            matches: self.matches(),
+        }
+        .serialize(s)
+    }
+}
+
+impl Record for CrossCheckRecord {
+    const COLUMNS: &'static [&'static str] = &["puzzle", "profile", "answer", "accepted", ""];
+
+    fn cells(&self) -> Vec<String> {
+        let answer = match &self.outcome {
+            Outcome::Solved(answer, _) => answer.clone(),
+            Outcome::Error(_) => "error".to_owned(),
+            Outcome::Panicked => "panicked".to_owned(),
+            Outcome::TimedOut(_) => "timed out".to_owned(),
+            Outcome::Crashed(_) => "crashed".to_owned(),
+        };
+        let verdict = match self.matches() {
+            Some(true) => "ok",
+            Some(false) => "DIFFERS",
+            None => "",
+        };
+        vec![
+            format!("{}.{}.{}", self.year, self.day, self.part),
+            self.profile.clone(),
+            answer,
+            self.accepted.clone().unwrap_or_else(|| "-".to_owned()),
+            verdict.to_owned(),
+        ]
+    }
+
+    fn plain(&self) -> String {
+        let prefix = format!(
+            "Puzzle {}.{}.{} for {}",
+            self.year, self.day, self.part, self.profile
+        );
+        match (&self.outcome, &self.accepted) {
+            (Outcome::Solved(solution, _), Some(accepted)) if solution == accepted => {
+                format!("{prefix} matches {accepted}")
+            }
+            (Outcome::Solved(solution, _), Some(accepted)) => {
+                format!("{prefix} = {solution}, but {accepted} was accepted")
+            }
+            (Outcome::Solved(solution, _), None) => {
+                format!("{prefix} = {solution}, not solved yet")
+            }
+            (Outcome::Error(e), _) => format!("{prefix} encountered an error: {e}"),
+            (Outcome::Panicked, _) => format!("{prefix} panicked"),
+            (Outcome::TimedOut(timeout), _) => format!("{prefix} timed out after {timeout:?}"),
+            (Outcome::Crashed(status), _) => format!("{prefix} crashed ({status})"),
+        }
+    }
+}
+
+/// Benchmark result of one puzzle, compared to the baseline if there is one.
+/// JSON: `year`, `day`, `part` and `error`, the `samples` and `outliers` of the whole call with
+/// its `min_ns`, `median_ns`, `mean_ns` and `stddev_ns`, the medians `parse_ns`, `unpack_ns`,
+/// `solve_ns` and `format_ns` of the phases, `allocations`, `bytes` and `peak_bytes` with
+/// `--alloc`, and `baseline_median_ns`, the relative `change` of the mean and its `verdict`
+/// with a baseline.
+#[derive(Clone, Debug)]
+pub struct BenchRecord {
+    year: Year,
+    day: Day,
+    part: Part,
+    report: Result<BenchReport, String>,
+    baseline: Option<Stats>,
+}
+
+impl BenchRecord {
+    pub fn new(
+        puzzle: &Puzzle,
+        report: Result<BenchReport, String>,
+        baseline: Option<Stats>,
+    ) -> Self {
+        Self {
+            year: puzzle.year(),
+            day: puzzle.day(),
+            part: puzzle.part(),
+            report,
+            baseline,
+        }
+    }
+
+    /// How many puzzles regressed, improved or stayed the same against the baseline
+    pub fn summary(records: &[BenchRecord]) -> String {
+        let count = |change: Change| {
+            records
+                .iter()
+                .filter(|x| x.change().is_some_and(|(_, x)| x == change))
+                .count()
+        };
+        let mut summary = format!(
+            "{} regressed, {} improved, {} unchanged",
+            count(Change::Regression),
+            count(Change::Improvement),
+            count(Change::Unchanged)
+        );
+        let missing = records.iter().filter(|x| x.change().is_none()).count();
+        if missing > 0 {
+            summary += &format!(", {missing} without a baseline");
+        }
+        summary
+    }
+
+    /// Relative change of the mean and whether it is significant
+    fn change(&self) -> Option<(f64, Change)> {
+        let (Ok(report), Some(baseline)) = (&self.report, &self.baseline) else {
+            return None;
+        };
+        Some((
+            report.total.relative_change(baseline),
+            report.total.compare(baseline),
+        ))
+    }
+}
+
+impl serde::Serialize for BenchRecord {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        #[derive(serde::Serialize)]
+        struct Json<'a> {
+            year: Year,
+            day: Day,
+            part: Part,
+            error: Option<&'a str>,
+            samples: Option<usize>,
+            outliers: Option<usize>,
+            #[serde(serialize_with = "nanos_opt")]
+            min_ns: Option<Duration>,
+            #[serde(serialize_with = "nanos_opt")]
+            median_ns: Option<Duration>,
+            #[serde(serialize_with = "nanos_opt")]
+            mean_ns: Option<Duration>,
+            #[serde(serialize_with = "nanos_opt")]
+            stddev_ns: Option<Duration>,
+            #[serde(serialize_with = "nanos_opt")]
+            parse_ns: Option<Duration>,
+            #[serde(serialize_with = "nanos_opt")]
+            unpack_ns: Option<Duration>,
+            #[serde(serialize_with = "nanos_opt")]
+            solve_ns: Option<Duration>,
+            #[serde(serialize_with = "nanos_opt")]
+            format_ns: Option<Duration>,
+            allocations: Option<u64>,
+            bytes: Option<u64>,
+            peak_bytes: Option<u64>,
+            #[serde(serialize_with = "nanos_opt")]
+            baseline_median_ns: Option<Duration>,
+            change: Option<f64>,
+            verdict: Option<Change>,
+        }
+        let report = self.report.as_ref().ok();
+        let alloc = report.and_then(|x| x.alloc);
+        let change = self.change();
+        Json {
+            year: self.year,
+            day: self.day,
+            part: self.part,
+            error: self.report.as_ref().err().map(String::as_str),
+            samples: report.map(|x| x.total.samples),
+            outliers: report.map(|x| x.total.outliers),
+            min_ns: report.map(|x| x.total.min),
+            median_ns: report.map(|x| x.total.median),
+            mean_ns: report.map(|x| x.total.mean),
+            stddev_ns: report.map(|x| x.total.stddev),
+            parse_ns: report.map(|x| x.parse.median),
+            unpack_ns: report.map(|x| x.unpack.median),
+            solve_ns: report.map(|x| x.solve.median),
+            format_ns: report.map(|x| x.format.median),
+            allocations: alloc.map(|x| x.allocations),
+            bytes: alloc.map(|x| x.bytes),
+            peak_bytes: alloc.map(|x| x.peak),
+            baseline_median_ns: self.baseline.map(|x| x.median),
+            change: change.map(|(change, _)| change),
+            verdict: change.map(|(_, verdict)| verdict),
+        }
+        .serialize(s)
+    }
+}
+
+impl Record for BenchRecord {
+    const COLUMNS: &'static [&'static str] = &[
+        "puzzle", "median", "parse", "unpack", "solve", "format", "baseline", "change", "",
+    ];
+
+    fn cells(&self) -> Vec<String> {
+        let puzzle = format!("{}.{}.{}", self.year, self.day, self.part);
+        let Ok(report) = &self.report else {
+            return vec![puzzle, "error".to_owned()];
+        };
+        let (change, verdict) = match self.change() {
+            Some((change, verdict)) => (format!("{:+.1}%", change * 100.0), verdict.to_string()),
+            None => ("-".to_owned(), String::new()),
+        };
+        vec![
+            puzzle,
+            duration(report.total.median),
+            duration(report.parse.median),
+            duration(report.unpack.median),
+            duration(report.solve.median),
+            duration(report.format.median),
+            self.baseline
+                .map(|x| duration(x.median))
+                .unwrap_or_else(|| "-".to_owned()),
+            change,
+            verdict,
+        ]
+    }
+
+    fn plain(&self) -> String {
+        let puzzle = format!("{}.{}.{}", self.year, self.day, self.part);
+        match (&self.report, self.change()) {
+            (Ok(report), Some((change, verdict))) => format!(
+                "Puzzle {puzzle} takes {report}, {:+.1}% against the baseline {verdict}",
+                change * 100.0
+            )
+            .trim_end()
+            .to_owned(),
+            (Ok(report), None) => format!("Puzzle {puzzle} takes {report}"),
+            (Err(e), _) => format!("Puzzle {puzzle} encountered an error: {e}"),
+        }
+    }
+}
+
+/// How a self-test ended, the flat JSON form of a [`SelfTestOutcome`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestStatus {
+    Passed,
+    Failed,
+    Error,
+    Panicked,
+    TimedOut,
+    Crashed,
+}
+
+/// Result of one self-test.
+/// JSON: `year`, `day`, `part`, the `index` of the test, its `status`, the `expected` output,
+/// what the solution returned instead or its error as `got`, and `elapsed_ns`.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct TestRecord {
+    year: Year,
+    day: Day,
+    part: Part,
+    index: usize,
+    status: TestStatus,
+    expected: &'static str,
+    got: Option<String>,
+    #[serde(rename = "elapsed_ns", serialize_with = "nanos")]
+    elapsed: Duration,
+    #[serde(skip)]
+    text: String,
+}
+
+impl From<&SelfTestResult> for TestRecord {
+    fn from(result: &SelfTestResult) -> Self {
+        let (status, got) = match result.outcome() {
+            SelfTestOutcome::Passed => (TestStatus::Passed, None),
+            SelfTestOutcome::Failed(got) => (TestStatus::Failed, Some(got.clone())),
+            SelfTestOutcome::Error(e) => (TestStatus::Error, Some(e.clone())),
+            SelfTestOutcome::Panicked => (TestStatus::Panicked, None),
+            SelfTestOutcome::TimedOut(timeout) => (
+                TestStatus::TimedOut,
+                Some(format!("timed out after {timeout:?}")),
+            ),
+            SelfTestOutcome::Crashed(status) => (TestStatus::Crashed, Some(status.clone())),
+        };
+        Self {
+            year: result.puzzle().year(),
+            day: result.puzzle().day(),
+            part: result.puzzle().part(),
+            index: result.index(),
+            status,
+            expected: result.test().output(),
+            got,
+            elapsed: result.elapsed(),
+            text: result.to_string(),
+        }
+    }
+}
+
+impl Record for TestRecord {
+    const COLUMNS: &'static [&'static str] = &["puzzle", "test", "result", "time"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            format!("{}.{}.{}", self.year, self.day, self.part),
+            format!("#{}", self.index),
+            if self.status == TestStatus::Passed {
+                "pass"
+            } else {
+                "FAIL"
+            }
+            .to_owned(),
+            duration(self.elapsed),
+        ]
+    }
+
+    fn plain(&self) -> String {
+        self.text.clone()
+    }
+}
+
+/// One cache entry, with its contents for `cache show`.
+/// JSON: `key`, `size` in bytes, `fetched_at` in seconds since the unix epoch and `source`,
+/// both `null` without metadata, and `content` for `cache show` only.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct CacheEntryRecord {
+    key: String,
+    size: u64,
+    fetched_at: Option<u64>,
+    source: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+}
+
+impl CacheEntryRecord {
+    pub fn new(entry: &CacheEntry, content: Option<String>) -> Self {
+        Self {
+            key: entry.key().to_string(),
+            size: entry.size(),
+            fetched_at: entry.metadata().map(|x| {
+                x.fetched_at()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs()
+            }),
+            source: entry.metadata().map(|x| x.source().to_owned()),
+            content,
+        }
+    }
+
+    fn age(&self) -> Option<u64> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        self.fetched_at.map(|x| now.saturating_sub(x))
+    }
+}
+
+impl Record for CacheEntryRecord {
+    const COLUMNS: &'static [&'static str] = &["key", "bytes", "age", "source"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.key.clone(),
+            self.size.to_string(),
+            self.age()
+                .map(|x| format!("{x}s"))
+                .unwrap_or_else(|| "-".to_owned()),
+            self.source.clone().unwrap_or_else(|| "-".to_owned()),
+        ]
+    }
+
+    fn plain(&self) -> String {
+        if let Some(content) = &self.content {
+            return format!("==> {} <==\n{content}", self.key);
+        }
+        match (self.age(), &self.source) {
+            (Some(age), Some(source)) => format!(
+                "{} ({} bytes, fetched {age}s ago from {source})",
+                self.key, self.size
+            ),
+            _ => format!("{} ({} bytes, missing metadata)", self.key, self.size),
+        }
+    }
+}
+
+/// What `cache clear`, `export` or `import` did.
+/// JSON: the `action`, the `count` of entries and the `dir` exported to or imported from.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct CacheCountRecord {
+    action: &'static str,
+    count: usize,
+    dir: Option<PathBuf>,
+}
+
+impl CacheCountRecord {
+    pub fn new(action: &'static str, count: usize, dir: Option<PathBuf>) -> Self {
+        Self { action, count, dir }
+    }
+}
+
+impl Record for CacheCountRecord {
+    const COLUMNS: &'static [&'static str] = &["action", "count", "dir"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.action.to_owned(),
+            self.count.to_string(),
+            self.dir
+                .as_ref()
+                .map(|x| x.display().to_string())
+                .unwrap_or_default(),
+        ]
+    }
+
+    fn plain(&self) -> String {
+        let verb = match self.action {
+            "clear" => "Removed",
+            "export" => "Exported",
+            _ => "Imported",
+        };
+        let direction = if self.action == "export" {
+            "to"
+        } else {
+            "from"
+        };
+        match &self.dir {
+            Some(dir) => format!(
+                "{verb} {} cache entries {direction} {}",
+                self.count,
+                dir.display()
+            ),
+            None => format!("{verb} {} cache entries", self.count),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{BenchRecord, OutputFormat, Printer, Record, RunRecord};
+    use crate::{
+        aoc::{get_puzzle, Timings},
+        util::{
+            bench::{BenchReport, Stats},
+            sandbox::Outcome,
+            SubmissionOutcome,
+        },
+    };
+
+    fn print<R: Record>(format: OutputFormat, records: &[R]) -> String {
+        let mut printer = Printer::with_writer(format, Vec::new());
+        for record in records {
+            printer.print(record).unwrap();
+        }
+        String::from_utf8(printer.finish().unwrap()).unwrap()
+    }
+
+    #[test]
+    pub fn test_printer_formats() {
+        let puzzle = get_puzzle(2019u16, 1u8, 1u8).unwrap();
+        let timings = Timings {
+            parse: Duration::from_nanos(100),
+            unpack: Duration::from_nanos(20),
+            solve: Duration::from_nanos(3000),
+            format: Duration::from_nanos(4),
+        };
+        let records = [
+            RunRecord::new(
+                &puzzle,
+                Outcome::Solved("42".to_owned(), timings),
+                Some(SubmissionOutcome::Correct),
+            ),
+            RunRecord::new(&puzzle, Outcome::Panicked, None),
+        ];
+        assert_eq!(
+            print(OutputFormat::Plain, &records),
+            "Solution for 2019.01.1 = 42 (parse 100.00ns, unpack 20.00ns, solve 3.00µs, format 4.00ns)\n\
+             Submission Result: Correct\n\
+             Puzzle 2019.01.1 panicked\n"
+        );
+        assert_eq!(
+            print(OutputFormat::Table, &records),
+            "puzzle     answer    time    submission\n\
+             2019.01.1  42        3.12µs  Correct\n\
+             2019.01.1  panicked\n"
+        );
+        let json: Vec<serde_json::Value> = print(OutputFormat::Json, &records)
+            .lines()
+            .map(|x| serde_json::from_str(x).unwrap())
+            .collect();
+        assert_eq!(
+            json[0],
+            serde_json::json!({
+                "year": 2019, "day": 1, "part": 1, "status": "solved", "answer": "42",
+                "error": null, "parse_ns": 100, "unpack_ns": 20, "solve_ns": 3000,
+                "format_ns": 4, "total_ns": 3124, "submission": "correct"
+            })
+        );
+        assert_eq!(json[1]["status"], "panicked");
+        assert_eq!(json[1]["total_ns"], serde_json::Value::Null);
+    }
+
+    #[test]
+    pub fn test_bench_summary() {
+        let puzzle = get_puzzle(2019u16, 1u8, 1u8).unwrap();
+        let stats = |mean: u64| Stats {
+            samples: 100,
+            mean: Duration::from_micros(mean),
+            median: Duration::from_micros(mean),
+            stddev: Duration::from_micros(1),
+            ..Default::default()
+        };
+        let report = |mean: u64| BenchReport {
+            total: stats(mean),
+            parse: Stats::default(),
+            unpack: Stats::default(),
+            solve: Stats::default(),
+            format: Stats::default(),
+            alloc: None,
+        };
+        let records = [
+            BenchRecord::new(&puzzle, Ok(report(200)), Some(stats(100))),
+            BenchRecord::new(&puzzle, Ok(report(100)), Some(stats(200))),
+            BenchRecord::new(&puzzle, Ok(report(300)), Some(stats(200))),
+            BenchRecord::new(&puzzle, Ok(report(100)), Some(stats(100))),
+            BenchRecord::new(&puzzle, Ok(report(100)), None),
+            BenchRecord::new(&puzzle, Err("panicked".to_owned()), Some(stats(100))),
+        ];
+        assert_eq!(
+            BenchRecord::summary(&records),
+            "2 regressed, 1 improved, 1 unchanged, 2 without a baseline"
+        );
+        assert_eq!(
+            BenchRecord::summary(&records[..1]),
+            "1 regressed, 0 improved, 0 unchanged"
+        );
+    }
+}
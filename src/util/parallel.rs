@@ -0,0 +1,47 @@
+use std::sync::Mutex;
+
+/// Runs `f` on all items with up to `jobs` threads and returns the results in the order
+/// of the items. With a single job everything runs on the current thread.
+pub fn map_ordered<T: Send, R: Send>(
+    jobs: usize,
+    items: impl IntoIterator<Item = T>,
+    f: impl Fn(T) -> R + Sync,
+) -> Vec<R> {
+    let items: Vec<T> = items.into_iter().collect();
+    let jobs = jobs.clamp(1, items.len().max(1));
+    if jobs == 1 {
+        return items.into_iter().map(f).collect();
+    }
+    let len = items.len();
+    let queue = Mutex::new(items.into_iter().enumerate());
+    let results = Mutex::new(Vec::with_capacity(len));
+    std::thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| loop {
+                // the lock is released before `f` runs, so the workers only contend for items
+                let Some((index, item)) = queue.lock().unwrap().next() else {
+                    break;
+                };
+                let result = f(item);
+                results.lock().unwrap().push((index, result));
+            });
+        }
+    });
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::map_ordered;
+
+    #[test]
+    pub fn test_map_ordered() {
+        let out = map_ordered(4, 0..100u64, |x| {
+            std::thread::sleep(std::time::Duration::from_micros(100 - x));
+            x * 2
+        });
+        assert_eq!(out, (0..100).map(|x| x * 2).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,83 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Error};
+
+use super::secret::{check_private, SecretString};
+
+/// Named session tokens, read from a TOML file like
+///
+/// ```toml
+/// default = "alice"
+///
+/// [profiles.alice]
+/// token = "53616c7465645f5f..."
+///
+/// [profiles.bob]
+/// token = "53616c7465645f5f..."
+/// ```
+#[derive(Default, serde::Deserialize)]
+pub struct Profiles {
+    /// Profile used when no `--profile` is given
+    default: Option<String>,
+    #[serde(default)]
+    profiles: BTreeMap<String, Profile>,
+}
+
+#[derive(Clone, serde::Deserialize)]
+pub struct Profile {
+    token: SecretString,
+}
+
+impl Profile {
+    pub fn token(&self) -> SecretString {
+        self.token.clone()
+    }
+}
+
+impl Profiles {
+    /// `$XDG_CONFIG_HOME/aoc/profiles.toml` or the platform equivalent
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|x| x.join("aoc").join("profiles.toml"))
+    }
+
+    /// Loads the profiles from `path`, a missing file means there are no profiles.
+    /// The file holds session tokens, so it must not be accessible by other users.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let data = match std::fs::read_to_string(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e).with_context(|| format!("could not read {}", path.display())),
+        };
+        check_private(path)?;
+        toml::from_str(&data).with_context(|| format!("could not parse {}", path.display()))
+    }
+
+    pub fn default_profile(&self) -> Option<&str> {
+        self.default.as_deref()
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Profile, Error> {
+        self.profiles
+            .get(name)
+            .with_context(|| format!("no profile named {name}"))
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.profiles.keys().map(|x| x.as_str())
+    }
+}
+
+/// Profile names become directory names, so only plain names are allowed
+pub fn check_profile_name(name: &str) -> Result<(), Error> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|x| x.is_ascii_alphanumeric() || x == '-' || x == '_')
+    {
+        anyhow::bail!("invalid profile name {name:?}, use letters, digits, - and _ only");
+    }
+    Ok(())
+}
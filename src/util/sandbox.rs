@@ -0,0 +1,236 @@
+use std::{
+    io::{Read, Write},
+    panic::catch_unwind,
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Error};
+
+use crate::aoc::{
+    get_puzzle, get_puzzle_test_inputs, puzzlespec::PuzzleSpec, self_test_outcome, Puzzle,
+    PuzzleTestInput, SelfTestOutcome, Timings,
+};
+
+/// Resource limits for a single puzzle call
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Limits {
+    pub timeout: Option<Duration>,
+    /// Bytes of address space the puzzle may use
+    pub memory: Option<u64>,
+}
+
+impl Limits {
+    /// Without limits puzzles run in-process, which is a lot cheaper
+    pub fn is_unlimited(&self) -> bool {
+        self.timeout.is_none() && self.memory.is_none()
+    }
+}
+
+/// How a puzzle call ended
+#[derive(Clone, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
+pub enum Outcome {
+    Solved(String, Timings),
+    Error(String),
+    Panicked,
+    TimedOut(Duration),
+    /// The subprocess died, most likely by hitting the memory limit
+    Crashed(String),
+}
+
+/// Runs the puzzle in this process, only panics are caught
+pub fn run_in_process(puzzle: &Puzzle, input: &str) -> Outcome {
+    match catch_unwind(|| puzzle.call_timed(input, None)) {
+        Ok(Ok((solution, timings))) => Outcome::Solved(solution, timings),
+        Ok(Err(e)) => Outcome::Error(format!("{e:?}")),
+        Err(_) => Outcome::Panicked,
+    }
+}
+
+/// Runs the puzzle within the limits, in a subprocess if there are any
+pub fn run(puzzle: &Puzzle, input: &str, limits: Limits) -> Outcome {
+    if limits.is_unlimited() {
+        return run_in_process(puzzle, input);
+    }
+    run_isolated(puzzle, input, limits).unwrap_or_else(|e| Outcome::Crashed(format!("{e:?}")))
+}
+
+/// Runs the puzzle through the hidden `exec` action of this executable
+fn run_isolated(puzzle: &Puzzle, input: &str, limits: Limits) -> Result<Outcome, Error> {
+    let mut command = Command::new(std::env::current_exe()?);
+    command.args(["exec", &puzzle.to_string()]);
+    Ok(run_command(command, input, limits)?.into())
+}
+
+/// Runs a self-test within the limits, in a subprocess if there are any
+pub fn run_test(
+    puzzle: &Puzzle,
+    test: &PuzzleTestInput,
+    index: usize,
+    limits: Limits,
+) -> SelfTestOutcome {
+    if limits.is_unlimited() {
+        return self_test_outcome(puzzle, test);
+    }
+    let isolated = std::env::current_exe()
+        .map_err(Error::from)
+        .and_then(|exe| {
+            let mut command = Command::new(exe);
+            command.args(["exec", &puzzle.to_string(), "--test", &index.to_string()]);
+            run_command(command, "", limits)
+        });
+    match isolated {
+        Ok(Isolated::Done(outcome)) => outcome,
+        Ok(Isolated::TimedOut(timeout)) => SelfTestOutcome::TimedOut(timeout),
+        Ok(Isolated::Crashed(status)) => SelfTestOutcome::Crashed(status),
+        Err(e) => SelfTestOutcome::Crashed(format!("{e:?}")),
+    }
+}
+
+/// How a subprocess run by [`run_command`] ended
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Isolated<T> {
+    /// The last line the process printed, parsed as JSON
+    Done(T),
+    TimedOut(Duration),
+    Crashed(String),
+}
+
+impl From<Isolated<Outcome>> for Outcome {
+    fn from(value: Isolated<Outcome>) -> Self {
+        match value {
+            Isolated::Done(outcome) => outcome,
+            Isolated::TimedOut(timeout) => Outcome::TimedOut(timeout),
+            Isolated::Crashed(status) => Outcome::Crashed(status),
+        }
+    }
+}
+
+/// Runs the command with `input` on stdin and the memory limit applied, and kills it once it
+/// exceeds the timeout
+pub fn run_command<T: serde::de::DeserializeOwned>(
+    mut command: Command,
+    input: &str,
+    limits: Limits,
+) -> Result<Isolated<T>, Error> {
+    command.stdin(Stdio::piped()).stdout(Stdio::piped());
+    #[cfg(unix)]
+    if let Some(memory) = limits.memory {
+        use std::os::unix::process::CommandExt;
+        // SAFETY: only calls setrlimit, which is async-signal-safe
+        unsafe {
+            command.pre_exec(move || {
+                let limit = libc::rlimit {
+                    rlim_cur: memory as libc::rlim_t,
+                    rlim_max: memory as libc::rlim_t,
+                };
+                if libc::setrlimit(libc::RLIMIT_AS, &limit) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+    let start = Instant::now();
+    let mut child = command.spawn().context("could not start puzzle process")?;
+    let mut stdin = child.stdin.take().context("puzzle process without stdin")?;
+    let input = input.to_owned();
+    // written and read on separate threads, so full pipes can't block the watchdog
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let mut stdout = child
+        .stdout
+        .take()
+        .context("puzzle process without stdout")?;
+    let reader = std::thread::spawn(move || {
+        let mut out = String::new();
+        stdout.read_to_string(&mut out).map(|_| out)
+    });
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if let Some(timeout) = limits.timeout.filter(|x| start.elapsed() > *x) {
+            child.kill()?;
+            child.wait()?;
+            return Ok(Isolated::TimedOut(timeout));
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    };
+    // the puzzle may exit without reading all of its input
+    let _ = writer.join();
+    let out = reader.join().unwrap_or(Ok(String::new()))?;
+    if !status.success() {
+        return Ok(Isolated::Crashed(status.to_string()));
+    }
+    // solutions may print their own output, the result is the last line
+    let result = out
+        .lines()
+        .last()
+        .context("puzzle process printed no result")?;
+    serde_json::from_str(result)
+        .map(Isolated::Done)
+        .context("could not parse result of puzzle process")
+}
+
+/// The `exec` action: runs one puzzle on the input from stdin, or one of its self-tests,
+/// and prints the outcome as JSON
+pub fn exec(spec: PuzzleSpec, test: Option<usize>) -> Result<(), Error> {
+    let (Some(year), Some(day), Some(part)) = (spec.year, spec.day, spec.part) else {
+        anyhow::bail!("exec needs a complete puzzle spec");
+    };
+    let puzzle = get_puzzle(year, day, part).context("no such puzzle")?;
+    if let Some(index) = test {
+        let test = get_puzzle_test_inputs(&puzzle)
+            .nth(index.saturating_sub(1))
+            .context("no such self-test")?;
+        println!(
+            "{}",
+            serde_json::to_string(&self_test_outcome(&puzzle, &test))?
+        );
+        return Ok(());
+    }
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input)?;
+    let outcome = run_in_process(&puzzle, &input);
+    println!("{}", serde_json::to_string(&outcome)?);
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use std::{process::Command, time::Duration};
+
+    use super::{run_command, Limits, Outcome};
+
+    fn sh(script: &str, limits: Limits) -> Outcome {
+        let mut command = Command::new("sh");
+        command.args(["-c", script]);
+        run_command(command, "", limits).unwrap().into()
+    }
+
+    #[test]
+    pub fn test_timeout() {
+        let limits = Limits {
+            timeout: Some(Duration::from_millis(200)),
+            memory: None,
+        };
+        assert_eq!(
+            sh("while :; do :; done", limits),
+            Outcome::TimedOut(Duration::from_millis(200))
+        );
+        assert_eq!(sh("echo '\"Panicked\"'", limits), Outcome::Panicked);
+    }
+
+    #[test]
+    pub fn test_memory_limit() {
+        let limits = Limits {
+            timeout: Some(Duration::from_secs(10)),
+            memory: Some(64 * 1024 * 1024),
+        };
+        let outcome = sh(
+            "x=$(head -c 200000000 /dev/zero | tr '\\0' a); echo '\"Panicked\"'",
+            limits,
+        );
+        assert!(matches!(outcome, Outcome::Crashed(_)), "{outcome:?}");
+    }
+}
@@ -0,0 +1,180 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Error};
+
+use crate::aoc::{calendar::unlock_time, day::Day, year::Year};
+
+const DAY_TEMPLATE: &str = include_str!("../../templates/day.rs.tmpl");
+
+/// Creates `aoc/year{year}/day{day}.rs` below `src` from the day template and declares the
+/// new modules, creating the year module if this is the first day of the year.
+/// `tests` are `add_test!` lines that are put below the `add_solution!` of the new day. They are
+/// commented out: the parts of the template fail until they are implemented, and failing
+/// self-tests would block running any puzzle.
+///
+/// Everything is checked and read before the first file is written, so a day that can't be
+/// created leaves the tree untouched.
+pub fn scaffold_day(src: &Path, year: Year, day: Day, tests: &[String]) -> Result<PathBuf, Error> {
+    if unlock_time(year, day).is_none() {
+        anyhow::bail!("{year} has no day {day}, only days 1 to 25");
+    }
+    let aoc_dir = src.join("aoc");
+    let aoc_mod = aoc_dir.join("mod.rs");
+    let year_dir = aoc_dir.join(format!("year{}", year.as_u16()));
+    let year_mod = year_dir.join("mod.rs");
+    let day_file = year_dir.join(format!("day{}.rs", day.as_u8()));
+    if day_file.exists() {
+        anyhow::bail!("{} already exists", day_file.display());
+    }
+
+    let new_year = !year_dir.exists();
+    let aoc_source = if new_year {
+        Some(with_mod_declaration(
+            &read(&aoc_mod)?,
+            "year",
+            year.as_u16().into(),
+        ))
+    } else {
+        None
+    };
+    let year_source = if new_year {
+        String::new()
+    } else {
+        read(&year_mod)?
+    };
+    let year_source = with_mod_declaration(&year_source, "day", day.as_u8().into());
+
+    let mut tests = tests
+        .iter()
+        .map(|x| {
+            if x.starts_with("//") {
+                format!("{x}\n")
+            } else {
+                format!("// {}\n", x.replace('\n', "\n// "))
+            }
+        })
+        .collect::<String>();
+    if !tests.is_empty() {
+        tests.insert_str(
+            0,
+            "// enable the tests and import add_test once the parts are implemented\n",
+        );
+    }
+    let source = DAY_TEMPLATE
+        .replace("{{TESTS}}", &tests)
+        .replace("{{YEAR}}", &year.as_u16().to_string())
+        .replace("{{DAY}}", &day.as_u8().to_string());
+
+    std::fs::create_dir_all(&year_dir)
+        .with_context(|| format!("could not create {}", year_dir.display()))?;
+    write(&day_file, &source)?;
+    write(&year_mod, &year_source)?;
+    if let Some(aoc_source) = aoc_source {
+        write(&aoc_mod, &aoc_source)?;
+    }
+    Ok(day_file)
+}
+
+fn read(file: &Path) -> Result<String, Error> {
+    std::fs::read_to_string(file).with_context(|| format!("could not read {}", file.display()))
+}
+
+fn write(file: &Path, source: &str) -> Result<(), Error> {
+    std::fs::write(file, source).with_context(|| format!("could not write {}", file.display()))
+}
+
+/// Inserts `mod {prefix}{number};` between the existing declarations with the same prefix,
+/// keeping them ordered by number
+fn with_mod_declaration(source: &str, prefix: &str, number: u32) -> String {
+    let declared = |line: &str| -> Option<u32> {
+        line.strip_prefix("mod ")?
+            .strip_suffix(';')?
+            .strip_prefix(prefix)?
+            .parse()
+            .ok()
+    };
+    let mut lines: Vec<&str> = source.lines().collect();
+    let new_line = format!("mod {prefix}{number};");
+    let position = lines
+        .iter()
+        .enumerate()
+        .filter_map(|(i, line)| declared(line).map(|n| (i, n)))
+        .take_while(|(_, n)| *n < number)
+        .last()
+        .map(|(i, _)| i + 1)
+        .or_else(|| lines.iter().position(|line| declared(line).is_some()))
+        .unwrap_or(0);
+    lines.insert(position, &new_line);
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod test {
+    use crate::aoc::{day::day, year::year};
+
+    use super::scaffold_day;
+
+    #[test]
+    pub fn test_scaffold_new_year() {
+        let src = tempfile::tempdir().unwrap();
+        let aoc = src.path().join("aoc");
+        std::fs::create_dir_all(aoc.join("year2023")).unwrap();
+        std::fs::write(
+            aoc.join("mod.rs"),
+            "mod year2019;\nmod year2023;\n\npub mod day;\n",
+        )
+        .unwrap();
+        std::fs::write(
+            aoc.join("year2023").join("mod.rs"),
+            "mod day1;\nmod day10;\n",
+        )
+        .unwrap();
+
+        scaffold_day(
+            src.path(),
+            year(2023),
+            day(2),
+            &["// add_test!(S, part 1, \"1\" => \"?\");".to_owned()],
+        )
+        .unwrap();
+        scaffold_day(
+            src.path(),
+            year(2024),
+            day(1),
+            &["add_test!(S, part 1, \"1\" => \"1\");".to_owned()],
+        )
+        .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(aoc.join("mod.rs")).unwrap(),
+            "mod year2019;\nmod year2023;\nmod year2024;\n\npub mod day;\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(aoc.join("year2023").join("mod.rs")).unwrap(),
+            "mod day1;\nmod day2;\nmod day10;\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(aoc.join("year2024").join("mod.rs")).unwrap(),
+            "mod day1;\n"
+        );
+        // the template fails its tests until it is implemented, so they start commented out
+        let source = std::fs::read_to_string(aoc.join("year2024").join("day1.rs")).unwrap();
+        assert!(source.contains("\n// add_test!(S, part 1, \"1\" => \"1\");\n"));
+        assert!(source.contains("    add_solution,\n"));
+        assert!(source.contains("(year(2024), day(1))"));
+        assert!(!source.contains("{{") && !source.contains("}}"));
+        let source = std::fs::read_to_string(aoc.join("year2023").join("day2.rs")).unwrap();
+        assert!(source.contains("\n// add_test!(S, part 1, \"1\" => \"?\");\n"));
+        assert!(!source.contains("// // "));
+        assert!(scaffold_day(src.path(), year(2023), day(2), &[]).is_err());
+
+        // nothing is written for a day that can't be created
+        let before = std::fs::read_to_string(aoc.join("mod.rs")).unwrap();
+        assert!(scaffold_day(src.path(), year(2030), day(99), &[]).is_err());
+        std::fs::remove_file(aoc.join("year2023").join("mod.rs")).unwrap();
+        assert!(scaffold_day(src.path(), year(2023), day(3), &[]).is_err());
+        assert!(!aoc.join("year2030").exists());
+        assert!(!aoc.join("year2023").join("day3.rs").exists());
+        assert_eq!(std::fs::read_to_string(aoc.join("mod.rs")).unwrap(), before);
+    }
+}
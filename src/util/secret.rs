@@ -0,0 +1,188 @@
+use std::{
+    ffi::OsStr,
+    path::Path,
+    process::Command,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{Context, Error};
+use zeroize::Zeroizing;
+
+/// AoC session cookies are valid for about a month after logging in
+const SESSION_LIFETIME: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Command used to look up the token in the secret service, `secret-tool` from libsecret
+/// unless overridden by `AOC_SECRET_TOOL`
+const SECRET_TOOL: &str = "secret-tool";
+
+/// A session token, zeroed once the last copy is dropped and never shown in `Debug` output
+#[derive(Clone)]
+pub struct SecretString {
+    secret: Arc<Zeroizing<String>>,
+    /// When the token was stored, if known, to detect expired sessions
+    issued: Option<SystemTime>,
+}
+
+impl std::str::FromStr for SecretString {
+    type Err = !;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::new(s.to_owned(), None))
+    }
+}
+
+impl std::fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SecretString(<redacted>)")
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for SecretString {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Self::new(String::deserialize(deserializer)?, None))
+    }
+}
+
+impl SecretString {
+    fn new(secret: String, issued: Option<SystemTime>) -> Self {
+        let secret = Zeroizing::new(secret);
+        Self {
+            secret: Arc::new(Zeroizing::new(secret.trim().to_owned())),
+            issued,
+        }
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.secret
+    }
+
+    /// Reads the token from a file that only its owner may access, the modification time
+    /// of the file is taken as the time the session was started
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        check_private(path)?;
+        let issued = std::fs::metadata(path)
+            .and_then(|x| x.modified())
+            .with_context(|| format!("could not read {}", path.display()))?;
+        let secret = std::fs::read_to_string(path)
+            .with_context(|| format!("could not read {}", path.display()))?;
+        Ok(Self::new(secret, Some(issued)))
+    }
+
+    /// Looks the token up in the freedesktop secret service
+    pub fn from_secret_service() -> Result<Self, Error> {
+        match std::env::var_os("AOC_SECRET_TOOL") {
+            Some(tool) => Self::from_secret_service_with(&tool),
+            None => Self::from_secret_service_with(OsStr::new(SECRET_TOOL)),
+        }
+    }
+
+    /// Looks the token up with `tool`, which takes the arguments of `secret-tool`
+    pub fn from_secret_service_with(tool: &OsStr) -> Result<Self, Error> {
+        let output = Command::new(tool)
+            .args(["lookup", "service", "adventofcode.com"])
+            .output()
+            .with_context(|| format!("could not run {}", tool.to_string_lossy()))?;
+        let secret = Zeroizing::new(output.stdout);
+        if !output.status.success() || secret.is_empty() {
+            anyhow::bail!("no session token found in the secret service");
+        }
+        let secret = std::str::from_utf8(&secret).context("session token is not valid UTF-8")?;
+        Ok(Self::new(secret.to_owned(), None))
+    }
+
+    /// Checks that the token looks like a session cookie and that the session hasn't expired.
+    /// Errors never contain the token itself.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.secret.len() < 64 || !self.secret.chars().all(|c| c.is_ascii_hexdigit()) {
+            anyhow::bail!("session token is not a hex encoded session cookie");
+        }
+        let expired = self
+            .issued
+            .is_some_and(|issued| issued.elapsed().unwrap_or_default() > SESSION_LIFETIME);
+        if expired {
+            anyhow::bail!(
+                "session token is older than 30 days and has likely expired, log in again"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Refuses files that other users can read, they contain session tokens
+#[cfg(unix)]
+pub fn check_private(path: &Path) -> Result<(), Error> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = std::fs::metadata(path)
+        .with_context(|| format!("could not read {}", path.display()))?
+        .permissions()
+        .mode();
+    if mode & 0o077 != 0 {
+        anyhow::bail!(
+            "{} is accessible by other users, restrict it with chmod 600",
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn check_private(_path: &Path) -> Result<(), Error> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(unix)]
+    use std::{ffi::OsStr, os::unix::fs::PermissionsExt};
+
+    use super::SecretString;
+
+    const TOKEN: &str = "53616c7465645f5f0123456789abcdef0123456789abcdef0123456789abcdef";
+
+    #[test]
+    pub fn test_secret_redacted() {
+        let secret: SecretString = TOKEN.parse().unwrap();
+        assert!(!format!("{secret:?}").contains(TOKEN));
+        assert!(secret.validate().is_ok());
+        let secret: SecretString = "not-a-token".parse().unwrap();
+        assert!(!format!("{:?}", secret.validate().unwrap_err()).contains("not-a-token"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    pub fn test_secret_file_permissions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("token");
+        std::fs::write(&path, format!("{TOKEN}\n")).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(SecretString::from_file(&path).is_err());
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+        let secret = SecretString::from_file(&path).unwrap();
+        assert_eq!(secret.expose(), TOKEN);
+        assert!(secret.validate().is_ok());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    pub fn test_secret_tool() {
+        let dir = tempfile::tempdir().unwrap();
+        let tool = dir.path().join("secret-tool");
+        // prints the token only when asked for it the way secret-tool is
+        std::fs::write(
+            &tool,
+            format!(
+                "#!/bin/sh\n[ \"$*\" = \"lookup service adventofcode.com\" ] || exit 1\necho {TOKEN}\n"
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&tool, std::fs::Permissions::from_mode(0o700)).unwrap();
+        let secret = SecretString::from_secret_service_with(tool.as_os_str()).unwrap();
+        assert_eq!(secret.expose(), TOKEN);
+        assert!(SecretString::from_secret_service_with(OsStr::new("false")).is_err());
+        assert!(
+            SecretString::from_secret_service_with(dir.path().join("missing").as_os_str()).is_err()
+        );
+    }
+}
@@ -0,0 +1,25 @@
+//! Helpers shared by the tests of several modules
+
+use std::{ffi::OsString, path::Path};
+
+use clap::Parser;
+
+use super::Arguments;
+
+/// Arguments that only use files below `dir`: the local backend in `dir/backend`, the cache in
+/// `dir/cache` and the profiles file `dir/profiles.toml`, so tests never read the user's own
+/// profiles. `extra` is appended and may contain an action.
+pub fn offline_args(dir: &Path, extra: &[&str]) -> Arguments {
+    let path = |name: &str| dir.join(name).into_os_string();
+    let mut args: Vec<OsString> = vec![
+        "aoc".into(),
+        "--backend-dir".into(),
+        path("backend"),
+        "--cache-dir".into(),
+        path("cache"),
+        "--profiles-file".into(),
+        path("profiles.toml"),
+    ];
+    args.extend(extra.iter().map(OsString::from));
+    Arguments::parse_from(args)
+}